@@ -0,0 +1,373 @@
+// The owned packet the server used before requests were parsed in place with
+// DHCPPacketView and replies were encoded into a buffer. Every option is
+// copied out of the request, long options are merged by concatenation and
+// replies are built up in vectors. Kept as a baseline for the benchmarks.
+use crate::{
+    dhcp::{DHCPOptionClass, HardwareType, MessageType, ParseMode},
+    IPAddress,
+};
+
+pub struct DHCPPacket {
+    message_type: MessageType,
+    hardware_type: HardwareType,
+    hardware_address_length: u8,
+    hops: u8,
+    transaction_id: u32,
+    seconds: u16,
+    flags: u16,
+    client_ip_address: IPAddress,
+    your_ip_address: IPAddress,
+    server_ip_address: IPAddress,
+    gateway_ip_address: IPAddress,
+    client_hardware_address: [u8; 16],
+    server_name: [u8; 64],
+    boot_file: [u8; 128],
+    options: Vec<DHCPOption>,
+    max_message_size: usize,
+}
+
+struct DHCPOption {
+    class: DHCPOptionClass,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum PacketParseError {
+    TooShort(usize),
+    NoEndOption(usize),
+    InvalidMagic([u8; 4]),
+    InvalidMessageType(u8),
+    ParseOptionError(usize),
+    InvalidOverload(usize, u8),
+}
+
+const SERVER_NAME_OFFSET: usize = 44;
+const BOOT_FILE_OFFSET: usize = 108;
+const MAGIC_OFFSET: usize = 236;
+const OPTIONS_OFFSET: usize = 240;
+
+const MAGIC: [u8; 4] = [99, 130, 83, 99];
+
+const OVERLOAD_BOOT_FILE: u8 = 1;
+const OVERLOAD_SERVER_NAME: u8 = 2;
+const OVERLOAD_BOTH: u8 = 3;
+
+const MIN_MAX_MESSAGE_SIZE: usize = 576;
+const IP_UDP_HEADER_SIZE: usize = 28;
+
+const MAX_OPTION_LENGTH: usize = 255;
+
+impl DHCPPacket {
+    pub fn parse(packet: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        if packet.len() < OPTIONS_OFFSET {
+            return Err(PacketParseError::TooShort(packet.len()));
+        }
+
+        if packet[MAGIC_OFFSET..OPTIONS_OFFSET] != MAGIC {
+            return Err(PacketParseError::InvalidMagic([
+                packet[MAGIC_OFFSET],
+                packet[MAGIC_OFFSET + 1],
+                packet[MAGIC_OFFSET + 2],
+                packet[MAGIC_OFFSET + 3],
+            ]));
+        }
+
+        // Parse basic information
+        let message_type = match MessageType::parse(packet[0]) {
+            Some(message_type) => message_type,
+            None => return Err(PacketParseError::InvalidMessageType(packet[0])),
+        };
+        let mut client_hardware_address = [0; 16];
+        client_hardware_address.copy_from_slice(&packet[28..SERVER_NAME_OFFSET]);
+        let mut server_name = [0; 64];
+        server_name.copy_from_slice(&packet[SERVER_NAME_OFFSET..BOOT_FILE_OFFSET]);
+        let mut boot_file = [0; 128];
+        boot_file.copy_from_slice(&packet[BOOT_FILE_OFFSET..MAGIC_OFFSET]);
+
+        // Parse options
+        let mut options = Vec::new();
+        let mut overload_offset = None;
+        parse_options(
+            packet,
+            OPTIONS_OFFSET,
+            packet.len(),
+            mode,
+            &mut options,
+            &mut overload_offset,
+        )?;
+
+        // Parse options overloaded into the boot file and server name fields
+        if let Some(offset) = overload_offset {
+            let overload = match options
+                .iter()
+                .find(|option| option.class == DHCPOptionClass::Overload)
+            {
+                Some(option) => option.value.first().copied().unwrap_or(0),
+                None => 0,
+            };
+            options.retain(|option| option.class != DHCPOptionClass::Overload);
+
+            match overload {
+                OVERLOAD_BOOT_FILE | OVERLOAD_SERVER_NAME | OVERLOAD_BOTH => {}
+                _ => return Err(PacketParseError::InvalidOverload(offset, overload)),
+            }
+
+            if overload & OVERLOAD_BOOT_FILE != 0 {
+                parse_options(
+                    packet,
+                    BOOT_FILE_OFFSET,
+                    MAGIC_OFFSET,
+                    mode,
+                    &mut options,
+                    &mut None,
+                )?;
+                boot_file = [0; 128];
+            }
+
+            if overload & OVERLOAD_SERVER_NAME != 0 {
+                parse_options(
+                    packet,
+                    SERVER_NAME_OFFSET,
+                    BOOT_FILE_OFFSET,
+                    mode,
+                    &mut options,
+                    &mut None,
+                )?;
+                server_name = [0; 64];
+            }
+        }
+
+        // Repeated options are the parts of one long option (RFC 3396)
+        let mut merged_options: Vec<DHCPOption> = Vec::new();
+        for option in options {
+            match merged_options
+                .iter_mut()
+                .find(|merged_option| merged_option.class == option.class)
+            {
+                Some(merged_option) => merged_option.value.extend(option.value),
+                None => merged_options.push(option),
+            }
+        }
+
+        Ok(DHCPPacket {
+            message_type,
+            hardware_type: HardwareType::parse(packet[1]),
+            hardware_address_length: packet[2],
+            hops: packet[3],
+            transaction_id: crate::slice_to_u32(&packet[4..]),
+            seconds: crate::slice_to_u16(&packet[8..]),
+            flags: crate::slice_to_u16(&packet[10..]),
+            client_ip_address: IPAddress::new([packet[12], packet[13], packet[14], packet[15]]),
+            your_ip_address: IPAddress::new([packet[16], packet[17], packet[18], packet[19]]),
+            server_ip_address: IPAddress::new([packet[20], packet[21], packet[22], packet[23]]),
+            gateway_ip_address: IPAddress::new([packet[24], packet[25], packet[26], packet[27]]),
+            client_hardware_address,
+            server_name,
+            boot_file,
+            options: merged_options,
+            max_message_size: MIN_MAX_MESSAGE_SIZE,
+        })
+    }
+
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size.max(MIN_MAX_MESSAGE_SIZE);
+    }
+
+    pub fn get_option(&self, option_class: DHCPOptionClass) -> Option<&[u8]> {
+        for option in &self.options {
+            if option.class == option_class {
+                return Some(&option.value);
+            }
+        }
+
+        None
+    }
+
+    pub fn generate(&self) -> Vec<u8> {
+        // First four bytes
+        let mut vec = vec![
+            self.message_type.generate(),
+            self.hardware_type.generate(),
+            self.hardware_address_length,
+            self.hops,
+        ];
+
+        // Multibyte parameters
+        vec.extend_from_slice(&crate::u32_to_slice(self.transaction_id));
+        vec.extend_from_slice(&crate::u16_to_slice(self.seconds));
+        vec.extend_from_slice(&crate::u16_to_slice(self.flags));
+        vec.extend_from_slice(self.client_ip_address.as_slice());
+        vec.extend_from_slice(self.your_ip_address.as_slice());
+        vec.extend_from_slice(self.server_ip_address.as_slice());
+        vec.extend_from_slice(self.gateway_ip_address.as_slice());
+        vec.extend_from_slice(&self.client_hardware_address);
+
+        let options = self.generate_options();
+
+        // Each area is the overload flag it sets, its contents and its size
+        let mut areas = vec![(
+            0,
+            Vec::new(),
+            self.max_message_size
+                .saturating_sub(IP_UDP_HEADER_SIZE + OPTIONS_OFFSET),
+        )];
+        let options_length: usize = options
+            .iter()
+            .map(|segments| segments.iter().map(|segment| segment.len()).sum::<usize>())
+            .sum();
+        if options_length + 1 > areas[0].2 {
+            // Leave room for the overload option
+            areas[0].2 = areas[0].2.saturating_sub(3);
+            if self.boot_file.iter().all(|byte| *byte == 0) {
+                areas.push((OVERLOAD_BOOT_FILE, Vec::new(), self.boot_file.len()));
+            }
+            if self.server_name.iter().all(|byte| *byte == 0) {
+                areas.push((OVERLOAD_SERVER_NAME, Vec::new(), self.server_name.len()));
+            }
+        }
+
+        // Every area needs room for an end option, options which can't be
+        // placed entirely are dropped
+        for segments in options {
+            let lengths: Vec<usize> = areas.iter().map(|(_, area, _)| area.len()).collect();
+
+            let mut first_area = 0;
+            let mut placed = true;
+            for segment in segments {
+                placed = false;
+                for (i, (_, area, size)) in areas.iter_mut().enumerate().skip(first_area) {
+                    if area.len() + segment.len() < *size {
+                        area.extend_from_slice(&segment);
+                        first_area = i;
+                        placed = true;
+                        break;
+                    }
+                }
+
+                if !placed {
+                    break;
+                }
+            }
+
+            if !placed {
+                for ((_, area, _), length) in areas.iter_mut().zip(lengths) {
+                    area.truncate(length);
+                }
+            }
+        }
+
+        let mut overload = 0;
+        let mut main_options = Vec::new();
+        let mut server_name = self.server_name;
+        let mut boot_file = self.boot_file;
+        for (flag, mut area, _) in areas {
+            area.push(DHCPOptionClass::End.generate());
+
+            match flag {
+                OVERLOAD_BOOT_FILE => {
+                    if area.len() > 1 {
+                        boot_file[..area.len()].copy_from_slice(&area);
+                        overload |= flag;
+                    }
+                }
+                OVERLOAD_SERVER_NAME => {
+                    if area.len() > 1 {
+                        server_name[..area.len()].copy_from_slice(&area);
+                        overload |= flag;
+                    }
+                }
+                _ => main_options = area,
+            }
+        }
+
+        // Server name and bootfile
+        vec.extend_from_slice(&server_name);
+        vec.extend_from_slice(&boot_file);
+
+        // Magic
+        vec.extend_from_slice(&MAGIC);
+
+        // Options
+        if overload != 0 {
+            vec.extend_from_slice(&[DHCPOptionClass::Overload.generate(), 1, overload]);
+        }
+        vec.extend_from_slice(&main_options);
+
+        vec
+    }
+
+    // Values longer than 255 bytes are split into consecutive options with
+    // the same code (RFC 3396)
+    fn generate_options(&self) -> Vec<Vec<Vec<u8>>> {
+        let mut options = Vec::new();
+
+        for option in &self.options {
+            match option.class {
+                DHCPOptionClass::Pad | DHCPOptionClass::End | DHCPOptionClass::Overload => continue,
+                _ => {}
+            }
+
+            if option.value.is_empty() {
+                options.push(vec![vec![option.class.generate(), 0]]);
+                continue;
+            }
+
+            let mut segments = Vec::new();
+            for chunk in option.value.chunks(MAX_OPTION_LENGTH) {
+                let mut vec = vec![option.class.generate(), chunk.len() as u8];
+                vec.extend_from_slice(chunk);
+                segments.push(vec);
+            }
+            options.push(segments);
+        }
+
+        options
+    }
+}
+
+// Parses the options in packet[start..end], recording where an overload
+// option was found
+fn parse_options(
+    packet: &[u8],
+    start: usize,
+    end: usize,
+    mode: ParseMode,
+    options: &mut Vec<DHCPOption>,
+    overload_offset: &mut Option<usize>,
+) -> Result<(), PacketParseError> {
+    let mut i = start;
+    while i < end {
+        let class = DHCPOptionClass::parse(packet[i]);
+
+        // Pad and end are a single byte without a length
+        match class {
+            DHCPOptionClass::End => return Ok(()),
+            DHCPOptionClass::Pad => {
+                i += 1;
+                continue;
+            }
+            DHCPOptionClass::Overload => *overload_offset = Some(i),
+            _ => {}
+        }
+
+        if i + 1 >= end {
+            return Err(PacketParseError::ParseOptionError(i));
+        }
+
+        let length = packet[i + 1] as usize;
+        if i + 2 + length > end {
+            return Err(PacketParseError::ParseOptionError(i));
+        }
+
+        options.push(DHCPOption {
+            class,
+            value: packet[i + 2..i + 2 + length].to_vec(),
+        });
+        i += 2 + length;
+    }
+
+    match mode {
+        ParseMode::Strict => Err(PacketParseError::NoEndOption(end)),
+        ParseMode::Lenient => Ok(()),
+    }
+}
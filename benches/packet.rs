@@ -0,0 +1,89 @@
+// Compares the borrowed view and the in place encoder the server uses against
+// the owned packet it used before. The server is a binary so its sources are
+// included directly.
+#![allow(dead_code, unused_imports)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[path = "../src/address.rs"]
+mod address;
+#[path = "../src/dhcp/mod.rs"]
+mod dhcp;
+#[path = "../src/util.rs"]
+mod util;
+
+mod baseline;
+
+pub use address::*;
+pub use util::*;
+
+use dhcp::{DHCPOptionClass, DHCPPacket, DHCPPacketView, HardwareAddress, HardwareType, ParseMode};
+
+// A typical request with a long option split over several instances
+fn sample_packet() -> DHCPPacket {
+    let mut packet = DHCPPacket::new(
+        0x12345678,
+        0x8000,
+        IPAddress::new([0, 0, 0, 0]),
+        IPAddress::new([192, 168, 1, 100]),
+        IPAddress::new([192, 168, 1, 1]),
+        IPAddress::new([0, 0, 0, 0]),
+        &HardwareAddress::new(
+            HardwareType::Ethernet,
+            &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+        ),
+    );
+
+    packet.add_option(DHCPOptionClass::DHCPMsgType, &[3]);
+    packet.add_option(
+        DHCPOptionClass::ClientID,
+        &[1, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+    );
+    packet.add_option(DHCPOptionClass::AddressRequest, &[192, 168, 1, 100]);
+    packet.add_option(DHCPOptionClass::ParameterList, &[1, 3, 6, 15, 28, 42, 51]);
+    packet.add_option(DHCPOptionClass::Hostname, b"workstation");
+    packet.add_option(DHCPOptionClass::VendorSpecific, &[0xAB; 300]);
+    packet.set_max_message_size(1500);
+
+    packet
+}
+
+fn parse(c: &mut Criterion) {
+    let bytes = sample_packet().generate();
+
+    c.bench_function("parse baseline", |b| {
+        b.iter(|| {
+            let packet = baseline::DHCPPacket::parse(black_box(&bytes), ParseMode::Strict).unwrap();
+            black_box(packet.get_option(DHCPOptionClass::ParameterList));
+            black_box(packet.get_option(DHCPOptionClass::VendorSpecific));
+        })
+    });
+
+    c.bench_function("parse view", |b| {
+        b.iter(|| {
+            let view = DHCPPacketView::parse(black_box(&bytes), ParseMode::Strict).unwrap();
+            black_box(view.get_option(DHCPOptionClass::ParameterList));
+            black_box(view.get_option(DHCPOptionClass::VendorSpecific));
+        })
+    });
+}
+
+fn generate(c: &mut Criterion) {
+    let packet = sample_packet();
+
+    let mut baseline = baseline::DHCPPacket::parse(&packet.generate(), ParseMode::Strict).unwrap();
+    baseline.set_max_message_size(1500);
+    c.bench_function("generate baseline", |b| {
+        b.iter(|| black_box(baseline.generate()))
+    });
+
+    c.bench_function("generate", |b| b.iter(|| black_box(packet.generate())));
+
+    let mut buffer = [0; 1500];
+    c.bench_function("encode", |b| {
+        b.iter(|| black_box(packet.encode(black_box(&mut buffer)).unwrap()))
+    });
+}
+
+criterion_group!(benches, parse, generate);
+criterion_main!(benches);
@@ -6,6 +6,15 @@ pub struct IPAddress([u8; 4]);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MACAddress([u8; 6]);
 
+// Identifies a client, either by the client identifier it sent or by its
+// hardware type followed by its hardware address (RFC 2132 section 9.14)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientID(Vec<u8>);
+
+// Marks a client identifier made from an Ethernet MAC address, which would
+// otherwise read the same as a six byte identifier in hex
+const MAC_CLIENT_ID_PREFIX: &str = "mac:";
+
 impl IPAddress {
     pub const fn new(address: [u8; 4]) -> Self {
         IPAddress(address)
@@ -38,46 +47,24 @@ impl IPAddress {
         ]))
     }
 
+    pub fn from_u32(address: u32) -> Self {
+        IPAddress(crate::u32_to_slice(address))
+    }
+
     pub fn as_slice(&self) -> &[u8] {
         &self.0
     }
 
-    pub fn to_socket_addr(&self, port: u16) -> SocketAddr {
-        SocketAddr::from((self.0, port))
+    pub fn to_u32(&self) -> u32 {
+        crate::slice_to_u32(&self.0)
     }
 
-    pub fn increament(&mut self) {
-        // Increament but ignore address's that satisfy one of the following conditions:
-        //  1 - x.x.x.255       (May be a broadcast address)
-        //  2 - x.x.255.255     (Same as above)
-        //  3 - x.255.255.255   (Same as above)
-        //  4 - 255.255.255.255 (Is broadcast address)
-        //  5 - x.x.x.0         (Some programs don't like client addresses ending in 0)
-
-        if self.0[3] == 255 {
-            self.0[3] = 0;
-            if self.0[2] == 255 {
-                self.0[2] = 0;
-                if self.0[1] == 255 {
-                    self.0[1] = 0;
-                    if self.0[0] == 255 {
-                        panic!("Overflow on increamenting I.P. Address");
-                    } else {
-                        self.0[0] += 1;
-                    }
-                } else {
-                    self.0[1] += 1;
-                }
-            } else {
-                self.0[2] += 1;
-            }
-        } else {
-            self.0[3] += 1;
-        }
+    pub fn network(&self, subnet_mask: IPAddress) -> IPAddress {
+        IPAddress::from_u32(self.to_u32() & subnet_mask.to_u32())
+    }
 
-        if self.0[3] == 255 || self.0[3] == 0 {
-            self.increament();
-        }
+    pub fn to_socket_addr(&self, port: u16) -> SocketAddr {
+        SocketAddr::from((self.0, port))
     }
 }
 
@@ -124,30 +111,12 @@ impl MACAddress {
         }
 
         Some(MACAddress([
-            match MACAddress::parse_part(parts[0]) {
-                Some(part) => part,
-                None => return None,
-            },
-            match MACAddress::parse_part(parts[1]) {
-                Some(part) => part,
-                None => return None,
-            },
-            match MACAddress::parse_part(parts[2]) {
-                Some(part) => part,
-                None => return None,
-            },
-            match MACAddress::parse_part(parts[3]) {
-                Some(part) => part,
-                None => return None,
-            },
-            match MACAddress::parse_part(parts[4]) {
-                Some(part) => part,
-                None => return None,
-            },
-            match MACAddress::parse_part(parts[5]) {
-                Some(part) => part,
-                None => return None,
-            },
+            MACAddress::parse_part(parts[0])?,
+            MACAddress::parse_part(parts[1])?,
+            MACAddress::parse_part(parts[2])?,
+            MACAddress::parse_part(parts[3])?,
+            MACAddress::parse_part(parts[4])?,
+            MACAddress::parse_part(parts[5])?,
         ]))
     }
 
@@ -185,3 +154,108 @@ impl std::fmt::Display for MACAddress {
         )
     }
 }
+
+impl ClientID {
+    pub fn new(id: Vec<u8>) -> Self {
+        ClientID(id)
+    }
+
+    pub fn from_hardware_address(hardware_type: u8, hardware_address: &[u8]) -> Self {
+        let mut id = vec![hardware_type];
+        id.extend_from_slice(hardware_address);
+        ClientID(id)
+    }
+
+    // Accepts "mac:" followed by the MAC address of an Ethernet client or the
+    // identifier in hex, identifiers can't be empty
+    pub fn parse<S: AsRef<str>>(str: S) -> Option<Self> {
+        let id = match str.as_ref().strip_prefix(MAC_CLIENT_ID_PREFIX) {
+            Some(mac_address) => {
+                return MACAddress::parse(mac_address)
+                    .map(|mac_address| ClientID::from_hardware_address(1, mac_address.as_slice()))
+            }
+            None => crate::parse_hex(str.as_ref())?,
+        };
+
+        if id.is_empty() {
+            None
+        } else {
+            Some(ClientID(id))
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ClientID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Ethernet clients are shown by their MAC address
+        if self.0.len() == 7 && self.0[0] == 1 {
+            return write!(
+                f,
+                "{}{}",
+                MAC_CLIENT_ID_PREFIX,
+                MACAddress::new([self.0[1], self.0[2], self.0[3], self.0[4], self.0[5], self.0[6]])
+            );
+        }
+
+        for byte in &self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_id_parse_hex() {
+        assert_eq!(
+            ClientID::parse("01:0A:0B:0C:0D:0E:0F"),
+            Some(ClientID::new(vec![1, 10, 11, 12, 13, 14, 15]))
+        );
+
+        // Six bytes of hex are an identifier, not a MAC address
+        assert_eq!(
+            ClientID::parse("0A:0B:0C:0D:0E:0F"),
+            Some(ClientID::new(vec![10, 11, 12, 13, 14, 15]))
+        );
+        assert_eq!(ClientID::parse("FF00"), Some(ClientID::new(vec![255, 0])));
+    }
+
+    #[test]
+    fn client_id_parse_mac_address() {
+        assert_eq!(
+            ClientID::parse("mac:0A:0B:0C:0D:0E:0F"),
+            Some(ClientID::from_hardware_address(
+                1,
+                &[10, 11, 12, 13, 14, 15]
+            ))
+        );
+        assert_eq!(ClientID::parse("mac:0A:0B:0C"), None);
+    }
+
+    #[test]
+    fn client_id_parse_rejects_invalid() {
+        assert_eq!(ClientID::parse(""), None);
+        assert_eq!(ClientID::parse("0x"), None);
+        assert_eq!(ClientID::parse("mac:"), None);
+        assert_eq!(ClientID::parse("0A0"), None);
+        assert_eq!(ClientID::parse("GG"), None);
+    }
+
+    #[test]
+    fn client_id_display_round_trips() {
+        for client_id in [
+            ClientID::from_hardware_address(1, &[10, 11, 12, 13, 14, 15]),
+            ClientID::from_hardware_address(6, &[10, 11, 12, 13, 14, 15]),
+            ClientID::new(vec![10, 11, 12, 13, 14, 15]),
+        ] {
+            assert_eq!(ClientID::parse(client_id.to_string()), Some(client_id));
+        }
+    }
+}
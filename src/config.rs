@@ -1,22 +1,69 @@
-use crate::{IPAddress, MACAddress};
-use std::env::args;
+use crate::{
+    dhcp::{
+        DHCPOptionClass, HardwareAddress, OptionDescriptor, OptionValue, ParseMode,
+        RelayAgentInformation,
+    },
+    ClientID, IPAddress, MACAddress,
+};
+use std::{env::args, path::PathBuf};
 
 #[derive(Debug)]
 pub struct Configuration {
-    lease_start_ip: IPAddress,
-    lease_final_ip: IPAddress,
-    gateway_ip: IPAddress,
+    subnets: Vec<Subnet>,
     our_ip: IPAddress,
-    subnet_mask: IPAddress,
-    broadcast_address: IPAddress,
     dns: IPAddress,
     dns_alternative: IPAddress,
-    reserved_ips: Vec<(MACAddress, IPAddress)>,
+    reservations: Vec<Reservation>,
+    excluded_ips: Vec<(IPAddress, IPAddress)>,
+    options: Vec<(DHCPOptionClass, OptionValue)>,
     address_time: u32,
     renewal_time: u32,
     rebinding_time: u32,
     offer_time: u64,
+    decline_time: u64,
     log_limit: Option<usize>,
+    lease_store: LeaseStoreType,
+    lease_file: PathBuf,
+    parse_mode: ParseMode,
+    boot_server: Option<IPAddress>,
+    boot_file: Option<String>,
+    dynamic_bootp: bool,
+    trusted_relays: Vec<IPAddress>,
+    interfaces: Vec<String>,
+}
+
+// A network addresses are handed out on. Each has its own pools of addresses
+// to lease, optional gateway, mask and options which are sent on top of the global
+// options. Subnets in the same shared network are on the same link.
+#[derive(Debug, Clone)]
+pub struct Subnet {
+    network: IPAddress,
+    gateway_ip: Option<IPAddress>,
+    subnet_mask: IPAddress,
+    broadcast_address: IPAddress,
+    pools: Vec<(IPAddress, IPAddress)>,
+    options: Vec<(DHCPOptionClass, OptionValue)>,
+    shared_network: Option<String>,
+}
+
+// A reservation matches a client by its client identifier, its MAC address or
+// by the switch port it is connected to, as reported by a relay agent in its
+// circuit ID and remote ID. Every key given must match.
+#[derive(Debug, Clone)]
+pub struct Reservation {
+    client_id: Option<ClientID>,
+    mac_address: Option<MACAddress>,
+    circuit_id: Option<Vec<u8>>,
+    remote_id: Option<Vec<u8>>,
+    ip_address: IPAddress,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LeaseStoreType {
+    Memory,
+    File,
+    #[cfg(feature = "sqlite")]
+    SQLite,
 }
 
 #[derive(Debug)]
@@ -24,8 +71,13 @@ pub enum ConfigurationError {
     LoadError(config::Error),
     InvalidIP(String),
     InvalidMAC(String),
+    InvalidHex(String, String),
+    InvalidClientID(String),
     InvalidTime(std::num::ParseIntError),
     InvalidLogLimit(String, std::num::ParseIntError),
+    InvalidLeaseStore(String),
+    InvalidBoolean(String, String),
+    BootFileTooLong(String),
     NoLeaseStartIP,
     NoLeaseEndIP,
     NoGatewayIP,
@@ -34,14 +86,27 @@ pub enum ConfigurationError {
     NoBroadcastAddress,
     NoDNS,
     NoAlternativeDNS,
-    NoReservedIP(MACAddress),
+    NoReservedIP(usize),
+    NoReservationKey(usize),
+    InvalidExcludedRange(IPAddress, IPAddress),
+    InvalidPoolRange(IPAddress, IPAddress),
+    PoolOutsideSubnet(IPAddress, IPAddress, String),
+    OverlappingSubnets(String, String),
+    MissingKey(String),
+    InvalidOptionValue(String, String, &'static OptionDescriptor),
+    OptionNotConfigurable(String),
     OpenLogFileError(String, std::io::Error),
 }
 
 const DEFAULT_ADDRESS_TIME: u32 = 60 * 60 * 48; // 2 Days
 const DEFAULT_OFFER_TIME: u64 = 30; // 30 Seconds
+const DEFAULT_DECLINE_TIME: u64 = 60 * 60 * 24; // 1 Day
 
-const DEFAULT_CONFIGURATION_PATH: &'static str = "./config";
+const DEFAULT_CONFIGURATION_PATH: &str = "./config";
+const DEFAULT_LEASE_FILE_PATH: &str = "./leases";
+
+// The boot file field is 128 bytes including its null terminator
+const MAX_BOOT_FILE_LENGTH: usize = 127;
 
 pub fn load_configuration() -> Result<Configuration, ConfigurationError> {
     let args: Vec<String> = args().collect();
@@ -52,53 +117,164 @@ pub fn load_configuration() -> Result<Configuration, ConfigurationError> {
         DEFAULT_CONFIGURATION_PATH
     })?;
 
-    let lease_start_ip = match configuration.get("lease.start") {
+    let our_ip = match configuration.get("us") {
         Some(str) => match IPAddress::parse(str) {
             Some(ip) => ip,
             None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
         },
-        None => return Err(ConfigurationError::NoLeaseStartIP),
+        None => return Err(ConfigurationError::NoOurIP),
     };
 
-    let lease_final_ip = match configuration.get("lease.final") {
-        Some(str) => match IPAddress::parse(str) {
-            Some(ip) => ip,
-            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
-        },
-        None => return Err(ConfigurationError::NoLeaseEndIP),
-    };
+    // Subnets are declared in a list, a single subnet may be given by the
+    // top level keys instead
+    let mut subnets = Vec::new();
+    let mut i = 0;
+    loop {
+        let prefix = format!("subnet.{}.", i);
 
-    let gateway_ip = match configuration.get("gateway") {
-        Some(str) => match IPAddress::parse(str) {
-            Some(ip) => ip,
-            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
-        },
-        None => return Err(ConfigurationError::NoGatewayIP),
-    };
+        // A subnet is declared by any of its keys, so a missing key is
+        // reported instead of ending the list
+        let declared = ["subnet mask", "network", "gateway", "pool.0.start"]
+            .iter()
+            .any(|key| configuration.get(&format!("{}{}", prefix, key)).is_some());
+        if !declared {
+            break;
+        }
 
-    let our_ip = match configuration.get("us") {
-        Some(str) => match IPAddress::parse(str) {
-            Some(ip) => ip,
-            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
-        },
-        None => return Err(ConfigurationError::NoOurIP),
-    };
+        let subnet_mask = match get_ip(&configuration, &format!("{}subnet mask", prefix))? {
+            Some(subnet_mask) => subnet_mask,
+            None => {
+                return Err(ConfigurationError::MissingKey(format!(
+                    "{}subnet mask",
+                    prefix
+                )))
+            }
+        };
 
-    let subnet_mask = match configuration.get("subnet mask") {
-        Some(str) => match IPAddress::parse(str) {
-            Some(ip) => ip,
-            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
-        },
-        None => return Err(ConfigurationError::NoSubnetMask),
-    };
+        // Subnets without a gateway give their network address instead
+        let gateway_ip = get_ip(&configuration, &format!("{}gateway", prefix))?;
+        let network = match get_ip(&configuration, &format!("{}network", prefix))? {
+            Some(network) => network.network(subnet_mask),
+            None => match gateway_ip {
+                Some(gateway_ip) => gateway_ip.network(subnet_mask),
+                None => return Err(ConfigurationError::MissingKey(format!("{}network", prefix))),
+            },
+        };
 
-    let broadcast_address = match configuration.get("broadcast") {
-        Some(str) => match IPAddress::parse(str) {
-            Some(ip) => ip,
-            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
-        },
-        None => return Err(ConfigurationError::NoBroadcastAddress),
-    };
+        let broadcast_address = match get_ip(&configuration, &format!("{}broadcast", prefix))? {
+            Some(broadcast_address) => broadcast_address,
+            None => IPAddress::from_u32(network.to_u32() | !subnet_mask.to_u32()),
+        };
+
+        let mut pools = Vec::new();
+        let mut j = 0;
+        while let Some(start) = get_ip(&configuration, &format!("{}pool.{}.start", prefix, j))? {
+            let end = match get_ip(&configuration, &format!("{}pool.{}.final", prefix, j))? {
+                Some(end) => end,
+                None => {
+                    return Err(ConfigurationError::MissingKey(format!(
+                        "{}pool.{}.final",
+                        prefix, j
+                    )))
+                }
+            };
+
+            pools.push((start, end));
+            j += 1;
+        }
+
+        let shared_network = configuration
+            .get(&format!("{}shared network", prefix))
+            .map(|shared_network| shared_network.to_owned());
+
+        subnets.push(Subnet {
+            network,
+            gateway_ip,
+            subnet_mask,
+            broadcast_address,
+            pools,
+            options: get_options(&configuration, &prefix)?,
+            shared_network,
+        });
+        i += 1;
+    }
+
+    if subnets.is_empty() {
+        let lease_start_ip = match configuration.get("lease.start") {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => ip,
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => return Err(ConfigurationError::NoLeaseStartIP),
+        };
+
+        let lease_final_ip = match configuration.get("lease.final") {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => ip,
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => return Err(ConfigurationError::NoLeaseEndIP),
+        };
+
+        let gateway_ip = match configuration.get("gateway") {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => ip,
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => return Err(ConfigurationError::NoGatewayIP),
+        };
+
+        let subnet_mask = match configuration.get("subnet mask") {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => ip,
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => return Err(ConfigurationError::NoSubnetMask),
+        };
+
+        let broadcast_address = match configuration.get("broadcast") {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => ip,
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => return Err(ConfigurationError::NoBroadcastAddress),
+        };
+
+        subnets.push(Subnet {
+            network: gateway_ip.network(subnet_mask),
+            gateway_ip: Some(gateway_ip),
+            subnet_mask,
+            broadcast_address,
+            pools: vec![(lease_start_ip, lease_final_ip)],
+            options: Vec::new(),
+            shared_network: None,
+        });
+    }
+
+    for (i, subnet) in subnets.iter().enumerate() {
+        for (start, end) in &subnet.pools {
+            if start > end {
+                return Err(ConfigurationError::InvalidPoolRange(*start, *end));
+            }
+
+            if !subnet.contains(*start) || !subnet.contains(*end) {
+                return Err(ConfigurationError::PoolOutsideSubnet(
+                    *start,
+                    *end,
+                    subnet.to_string(),
+                ));
+            }
+        }
+
+        for other in &subnets[..i] {
+            if subnet.contains(other.network()) || other.contains(subnet.network()) {
+                return Err(ConfigurationError::OverlappingSubnets(
+                    other.to_string(),
+                    subnet.to_string(),
+                ));
+            }
+        }
+    }
 
     let dns = match configuration.get("dns.1") {
         Some(str) => match IPAddress::parse(str) {
@@ -117,28 +293,92 @@ pub fn load_configuration() -> Result<Configuration, ConfigurationError> {
     };
 
     let mut i = 0;
-    let mut reserved_ips = Vec::new();
+    let mut reservations = Vec::new();
     loop {
         let mac_address = match configuration.get(&format!("reserved.{}.mac", i)) {
             Some(str) => match MACAddress::parse(str) {
-                Some(mac_address) => mac_address,
+                Some(mac_address) => Some(mac_address),
                 None => return Err(ConfigurationError::InvalidMAC(str.to_owned())),
             },
-            None => break,
+            None => None,
         };
 
+        let client_id = match configuration.get(&format!("reserved.{}.client id", i)) {
+            Some(str) => match ClientID::parse(str) {
+                Some(client_id) => Some(client_id),
+                None => return Err(ConfigurationError::InvalidClientID(str.to_owned())),
+            },
+            None => None,
+        };
+        let circuit_id = get_hex(&configuration, &format!("reserved.{}.circuit id", i))?;
+        let remote_id = get_hex(&configuration, &format!("reserved.{}.remote id", i))?;
+
         let ip_address = match configuration.get(&format!("reserved.{}.ip", i)) {
+            Some(str) => match IPAddress::parse(str) {
+                Some(ip) => Some(ip),
+                None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+            },
+            None => None,
+        };
+
+        let has_key = client_id.is_some()
+            || mac_address.is_some()
+            || circuit_id.is_some()
+            || remote_id.is_some();
+        let ip_address = match ip_address {
+            Some(ip_address) => {
+                if !has_key {
+                    return Err(ConfigurationError::NoReservationKey(i));
+                }
+
+                ip_address
+            }
+            None => {
+                if has_key {
+                    return Err(ConfigurationError::NoReservedIP(i));
+                }
+
+                break;
+            }
+        };
+
+        reservations.push(Reservation {
+            client_id,
+            mac_address,
+            circuit_id,
+            remote_id,
+            ip_address,
+        });
+        i += 1;
+    }
+
+    let mut i = 0;
+    let mut excluded_ips = Vec::new();
+    while let Some(str) = configuration.get(&format!("exclude.{}.start", i)) {
+        let start = match IPAddress::parse(str) {
+            Some(ip) => ip,
+            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+        };
+
+        // A single address may be excluded by leaving out the final address
+        let end = match configuration.get(&format!("exclude.{}.final", i)) {
             Some(str) => match IPAddress::parse(str) {
                 Some(ip) => ip,
                 None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
             },
-            None => return Err(ConfigurationError::NoReservedIP(mac_address)),
+            None => start,
         };
 
-        reserved_ips.push((mac_address, ip_address));
+        if start > end {
+            return Err(ConfigurationError::InvalidExcludedRange(start, end));
+        }
+
+        excluded_ips.push((start, end));
         i += 1;
     }
 
+    let options = get_options(&configuration, "")?;
+
     let address_time = match configuration.get("lease.time") {
         Some(str) => match str.parse() {
             Ok(value) => value,
@@ -171,6 +411,14 @@ pub fn load_configuration() -> Result<Configuration, ConfigurationError> {
         None => DEFAULT_OFFER_TIME,
     };
 
+    let decline_time = match configuration.get("decline time") {
+        Some(str) => match str.parse() {
+            Ok(value) => value,
+            Err(error) => return Err(ConfigurationError::InvalidTime(error)),
+        },
+        None => DEFAULT_DECLINE_TIME,
+    };
+
     let log_limit = match configuration.get("log limit") {
         Some(limit) => match limit.parse() {
             Ok(value) => Some(value),
@@ -179,73 +427,270 @@ pub fn load_configuration() -> Result<Configuration, ConfigurationError> {
         None => None,
     };
 
-    // Update logging output
-    match configuration.get("log") {
+    let lease_store = match configuration.get("lease store") {
+        Some(store) => match store.as_str() {
+            "memory" => LeaseStoreType::Memory,
+            "file" => LeaseStoreType::File,
+            #[cfg(feature = "sqlite")]
+            "sqlite" => LeaseStoreType::SQLite,
+            _ => return Err(ConfigurationError::InvalidLeaseStore(store.to_owned())),
+        },
+        None => LeaseStoreType::File,
+    };
+
+    let lease_file = PathBuf::from(match configuration.get("lease file") {
+        Some(file) => file.as_str(),
+        None => DEFAULT_LEASE_FILE_PATH,
+    });
+
+    let parse_mode = match configuration.get("lenient parsing") {
+        Some(value) => match value.as_str() {
+            "true" => ParseMode::Lenient,
+            "false" => ParseMode::Strict,
+            _ => {
+                return Err(ConfigurationError::InvalidBoolean(
+                    "lenient parsing".to_owned(),
+                    value.to_owned(),
+                ))
+            }
+        },
+        None => ParseMode::Strict,
+    };
+
+    let boot_server = match configuration.get("boot.server") {
+        Some(str) => match IPAddress::parse(str) {
+            Some(ip) => Some(ip),
+            None => return Err(ConfigurationError::InvalidIP(str.to_owned())),
+        },
+        None => None,
+    };
+
+    let boot_file = match configuration.get("boot.file") {
         Some(file) => {
-            let root_logger = logging::get_logger("");
-            let mut handler = logging::Handler::new(match logging::FileHandler::new(file) {
-                Ok(handler) => handler,
-                Err(error) => {
-                    return Err(ConfigurationError::OpenLogFileError(file.to_owned(), error))
-                }
-            });
-            handler.set_formatter(Some(super::log_formatter));
+            if file.len() > MAX_BOOT_FILE_LENGTH {
+                return Err(ConfigurationError::BootFileTooLong(file.to_owned()));
+            }
+
+            Some(file.to_owned())
+        }
+        None => None,
+    };
+
+    let dynamic_bootp = match configuration.get("dynamic bootp") {
+        Some(value) => match value.as_str() {
+            "true" => true,
+            "false" => false,
+            _ => {
+                return Err(ConfigurationError::InvalidBoolean(
+                    "dynamic bootp".to_owned(),
+                    value.to_owned(),
+                ))
+            }
+        },
+        None => false,
+    };
 
-            root_logger.remove_handler(0);
-            root_logger.add_handler(handler);
+    // Relays which may choose the client's subnet, a comma separated list
+    let mut trusted_relays = Vec::new();
+    if let Some(str) = configuration.get("trusted relays") {
+        for relay in str.split(',') {
+            match IPAddress::parse(relay.trim()) {
+                Some(ip) => trusted_relays.push(ip),
+                None => return Err(ConfigurationError::InvalidIP(relay.trim().to_owned())),
+            }
         }
-        None => {}
+    }
+
+    // Interfaces to answer requests on, a comma separated list of names
+    let interfaces = match configuration.get("interfaces") {
+        Some(str) => str
+            .split(',')
+            .map(|interface| interface.trim().to_owned())
+            .filter(|interface| !interface.is_empty())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    // Update logging output
+    if let Some(file) = configuration.get("log") {
+        let root_logger = logging::get_logger("");
+        let mut handler = logging::Handler::new(match logging::FileHandler::new(file) {
+            Ok(handler) => handler,
+            Err(error) => return Err(ConfigurationError::OpenLogFileError(file.to_owned(), error)),
+        });
+        handler.set_formatter(Some(super::log_formatter));
+
+        root_logger.remove_handler(0);
+        root_logger.add_handler(handler);
     }
 
     Ok(Configuration {
-        lease_start_ip,
-        lease_final_ip,
-        gateway_ip,
+        subnets,
         our_ip,
-        subnet_mask,
-        broadcast_address,
         dns,
         dns_alternative,
-        reserved_ips,
+        reservations,
+        excluded_ips,
+        options,
         address_time,
         renewal_time,
         rebinding_time,
         offer_time,
+        decline_time,
         log_limit,
+        lease_store,
+        lease_file,
+        parse_mode,
+        boot_server,
+        boot_file,
+        dynamic_bootp,
+        trusted_relays,
+        interfaces,
     })
 }
 
-impl Configuration {
-    pub fn reserved_ips(&self) -> &Vec<(MACAddress, IPAddress)> {
-        &self.reserved_ips
+// Additional options can be given by either their name or code, "prefix" is
+// put before each key
+fn get_options(
+    configuration: &config::Configuration,
+    prefix: &str,
+) -> Result<Vec<(DHCPOptionClass, OptionValue)>, ConfigurationError> {
+    let mut options = Vec::new();
+    for code in 1..=254 {
+        let option_class = DHCPOptionClass::parse(code);
+
+        let mut keys = vec![format!("{}option.{}", prefix, code)];
+        match option_class {
+            DHCPOptionClass::PrivateUse(_) | DHCPOptionClass::Other(_) => {}
+            _ => keys.push(format!(
+                "{}option.{}",
+                prefix,
+                option_class.to_string().to_lowercase()
+            )),
+        }
+
+        for key in keys {
+            let value = match configuration.get(&key) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if !option_class.is_configurable() {
+                return Err(ConfigurationError::OptionNotConfigurable(key));
+            }
+
+            match OptionValue::parse(option_class.descriptor(), value) {
+                Some(value) => options.push((option_class, value)),
+                None => {
+                    return Err(ConfigurationError::InvalidOptionValue(
+                        key,
+                        value.to_owned(),
+                        option_class.descriptor(),
+                    ))
+                }
+            }
+        }
     }
 
-    pub fn our_ip(&self) -> IPAddress {
-        self.our_ip
+    Ok(options)
+}
+
+fn get_ip(
+    configuration: &config::Configuration,
+    key: &str,
+) -> Result<Option<IPAddress>, ConfigurationError> {
+    match configuration.get(key) {
+        Some(str) => match IPAddress::parse(str) {
+            Some(ip) => Ok(Some(ip)),
+            None => Err(ConfigurationError::InvalidIP(str.to_owned())),
+        },
+        None => Ok(None),
     }
+}
 
-    pub fn gateway_ip(&self) -> IPAddress {
-        self.gateway_ip
+// Binary values are written in hex, the same as binary options
+fn get_hex(
+    configuration: &config::Configuration,
+    key: &str,
+) -> Result<Option<Vec<u8>>, ConfigurationError> {
+    match configuration.get(key) {
+        Some(value) => match crate::parse_hex(value) {
+            Some(bytes) => Ok(Some(bytes)),
+            None => Err(ConfigurationError::InvalidHex(
+                key.to_owned(),
+                value.to_owned(),
+            )),
+        },
+        None => Ok(None),
     }
+}
 
-    pub fn subnet_mask(&self) -> IPAddress {
-        self.subnet_mask
+impl Configuration {
+    // A single subnet leasing the whole of 192.168.1.0/24 with every other
+    // setting at its default, for the lease store tests
+    #[cfg(test)]
+    pub fn test(lease_file: PathBuf) -> Self {
+        let subnet_mask = IPAddress::new([255, 255, 255, 0]);
+        let network = IPAddress::new([192, 168, 1, 0]);
+
+        Configuration {
+            subnets: vec![Subnet {
+                network,
+                gateway_ip: Some(IPAddress::new([192, 168, 1, 1])),
+                subnet_mask,
+                broadcast_address: IPAddress::new([192, 168, 1, 255]),
+                pools: vec![(network, IPAddress::new([192, 168, 1, 255]))],
+                options: Vec::new(),
+                shared_network: None,
+            }],
+            our_ip: IPAddress::new([192, 168, 1, 1]),
+            dns: IPAddress::new([192, 168, 1, 1]),
+            dns_alternative: IPAddress::new([192, 168, 1, 1]),
+            reservations: Vec::new(),
+            excluded_ips: vec![(
+                IPAddress::new([192, 168, 1, 1]),
+                IPAddress::new([192, 168, 1, 1]),
+            )],
+            options: Vec::new(),
+            address_time: DEFAULT_ADDRESS_TIME,
+            renewal_time: DEFAULT_ADDRESS_TIME / 2,
+            rebinding_time: (DEFAULT_ADDRESS_TIME / 4) * 3,
+            offer_time: DEFAULT_OFFER_TIME,
+            decline_time: DEFAULT_DECLINE_TIME,
+            log_limit: None,
+            lease_store: LeaseStoreType::File,
+            lease_file,
+            parse_mode: ParseMode::Strict,
+            boot_server: None,
+            boot_file: None,
+            dynamic_bootp: false,
+            trusted_relays: Vec::new(),
+            interfaces: Vec::new(),
+        }
     }
 
-    pub fn broadcast_address(&self) -> IPAddress {
-        self.broadcast_address
+    pub fn subnets(&self) -> &Vec<Subnet> {
+        &self.subnets
     }
 
-    pub fn dns(&self) -> (IPAddress, IPAddress) {
-        (self.dns, self.dns_alternative)
+    pub fn reservations(&self) -> &Vec<Reservation> {
+        &self.reservations
     }
 
-    pub fn lease_start_ip(&self) -> IPAddress {
-        self.lease_start_ip
+    pub fn excluded_ips(&self) -> &Vec<(IPAddress, IPAddress)> {
+        &self.excluded_ips
     }
 
-    pub fn lease_final_ip(&self) -> IPAddress {
-        self.lease_final_ip
+    pub fn options(&self) -> &Vec<(DHCPOptionClass, OptionValue)> {
+        &self.options
+    }
+
+    pub fn our_ip(&self) -> IPAddress {
+        self.our_ip
+    }
+
+    pub fn dns(&self) -> (IPAddress, IPAddress) {
+        (self.dns, self.dns_alternative)
     }
 
     pub fn address_time(&self) -> u32 {
@@ -264,31 +709,251 @@ impl Configuration {
         self.offer_time
     }
 
+    pub fn decline_time(&self) -> u64 {
+        self.decline_time
+    }
+
     pub fn log_limit(&self) -> Option<usize> {
         self.log_limit
     }
+
+    pub fn lease_store(&self) -> LeaseStoreType {
+        self.lease_store
+    }
+
+    pub fn lease_file(&self) -> &PathBuf {
+        &self.lease_file
+    }
+
+    pub fn parse_mode(&self) -> ParseMode {
+        self.parse_mode
+    }
+
+    // The server clients boot from, we are the boot server if none is given
+    pub fn boot_server(&self) -> IPAddress {
+        match self.boot_server {
+            Some(boot_server) => boot_server,
+            None => self.our_ip,
+        }
+    }
+
+    pub fn boot_file(&self) -> Option<&str> {
+        self.boot_file.as_deref()
+    }
+
+    pub fn dynamic_bootp(&self) -> bool {
+        self.dynamic_bootp
+    }
+
+    pub fn trusted_relays(&self) -> &Vec<IPAddress> {
+        &self.trusted_relays
+    }
+
+    // Empty if requests are answered on every interface
+    pub fn interfaces(&self) -> &Vec<String> {
+        &self.interfaces
+    }
 }
 
 impl std::fmt::Display for Configuration {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "  Lease:")?;
-        writeln!(f, "    Start: {}", self.lease_start_ip)?;
-        writeln!(f, "    End: {}", self.lease_final_ip)?;
         writeln!(f, "    Time: {}", self.address_time)?;
+        writeln!(f, "    Store: {}", self.lease_store)?;
+        writeln!(f, "    File: {}", self.lease_file.display())?;
         writeln!(f, "  Our I.P.: {}", self.our_ip)?;
-        writeln!(f, "  Gateway I.P.: {}", self.gateway_ip)?;
-        writeln!(f, "  Subnet Mask: {}", self.subnet_mask)?;
-        writeln!(f, "  Broadcast Address: {}", self.broadcast_address)?;
         writeln!(f, "  DNS: ({}, {})", self.dns, self.dns_alternative)?;
 
+        writeln!(f, "  Subnets:")?;
+        for subnet in &self.subnets {
+            writeln!(f, "    {}:", subnet)?;
+            if let Some(gateway_ip) = subnet.gateway_ip {
+                writeln!(f, "      Gateway I.P.: {}", gateway_ip)?;
+            }
+            writeln!(f, "      Broadcast Address: {}", subnet.broadcast_address)?;
+            if let Some(shared_network) = &subnet.shared_network {
+                writeln!(f, "      Shared Network: {}", shared_network)?;
+            }
+            for (start, end) in &subnet.pools {
+                writeln!(f, "      Pool: {} - {}", start, end)?;
+            }
+            for (option_class, value) in &subnet.options {
+                writeln!(f, "      {} -> {}", option_class, value)?;
+            }
+        }
+
+        writeln!(f, "  Options:")?;
+        for (option_class, value) in &self.options {
+            writeln!(f, "    {} -> {}", option_class, value)?;
+        }
+
         writeln!(f, "  Reservations:")?;
-        for (mac, ip) in &self.reserved_ips {
-            writeln!(f, "    {} -> {}", mac, ip)?;
+        for reservation in &self.reservations {
+            writeln!(f, "    {} -> {}", reservation, reservation.ip_address())?;
+        }
+
+        writeln!(f, "  Exclusions:")?;
+        for (start, end) in &self.excluded_ips {
+            writeln!(f, "    {} - {}", start, end)?;
         }
 
         writeln!(f, "Renewal Time: {}", self.renewal_time)?;
         writeln!(f, "Rebinding Time: {}", self.rebinding_time)?;
-        writeln!(f, "Offer Time: {}", self.offer_time)
+        writeln!(f, "Offer Time: {}", self.offer_time)?;
+        writeln!(f, "Decline Time: {}", self.decline_time)?;
+        writeln!(f, "Boot Server: {}", self.boot_server())?;
+        if let Some(boot_file) = &self.boot_file {
+            writeln!(f, "Boot File: {}", boot_file)?;
+        }
+        writeln!(f, "Dynamic BOOTP: {}", self.dynamic_bootp)?;
+        writeln!(f, "Interfaces:")?;
+        for interface in &self.interfaces {
+            writeln!(f, "    {}", interface)?;
+        }
+        writeln!(f, "Trusted Relays:")?;
+        for relay in &self.trusted_relays {
+            writeln!(f, "    {}", relay)?;
+        }
+        writeln!(f, "Parse Mode: {:?}", self.parse_mode)
+    }
+}
+
+impl Subnet {
+    pub fn network(&self) -> IPAddress {
+        self.network
+    }
+
+    pub fn contains(&self, ip_address: IPAddress) -> bool {
+        ip_address.network(self.subnet_mask) == self.network()
+    }
+
+    pub fn last_address(&self) -> IPAddress {
+        IPAddress::from_u32(self.network().to_u32() | !self.subnet_mask.to_u32())
+    }
+
+    pub fn gateway_ip(&self) -> Option<IPAddress> {
+        self.gateway_ip
+    }
+
+    pub fn subnet_mask(&self) -> IPAddress {
+        self.subnet_mask
+    }
+
+    pub fn broadcast_address(&self) -> IPAddress {
+        self.broadcast_address
+    }
+
+    pub fn pools(&self) -> &Vec<(IPAddress, IPAddress)> {
+        &self.pools
+    }
+
+    pub fn options(&self) -> &Vec<(DHCPOptionClass, OptionValue)> {
+        &self.options
+    }
+
+    pub fn shared_network(&self) -> Option<&str> {
+        self.shared_network.as_deref()
+    }
+}
+
+impl std::fmt::Display for Subnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network(), self.subnet_mask)
+    }
+}
+
+impl Reservation {
+    pub fn ip_address(&self) -> IPAddress {
+        self.ip_address
+    }
+
+    // Port reservations only match clients whose requests were relayed
+    pub fn matches(
+        &self,
+        client_id: &ClientID,
+        hardware_address: &HardwareAddress,
+        relay_agent_information: Option<&RelayAgentInformation>,
+    ) -> bool {
+        if let Some(reserved_client_id) = &self.client_id {
+            if reserved_client_id != client_id {
+                return false;
+            }
+        }
+
+        if let Some(reserved_mac_address) = self.mac_address {
+            if hardware_address.mac_address() != Some(reserved_mac_address) {
+                return false;
+            }
+        }
+
+        if let Some(circuit_id) = &self.circuit_id {
+            match relay_agent_information {
+                Some(relay_agent_information) => {
+                    if relay_agent_information.circuit_id() != Some(circuit_id.as_slice()) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+
+        if let Some(remote_id) = &self.remote_id {
+            match relay_agent_information {
+                Some(relay_agent_information) => {
+                    if relay_agent_information.remote_id() != Some(remote_id.as_slice()) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+
+        true
+    }
+}
+
+impl std::fmt::Display for Reservation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys = Vec::new();
+
+        if let Some(client_id) = &self.client_id {
+            keys.push(format!("Client ID {}", client_id));
+        }
+
+        if let Some(mac_address) = self.mac_address {
+            keys.push(format!("MAC {}", mac_address));
+        }
+
+        if let Some(circuit_id) = &self.circuit_id {
+            keys.push(format!(
+                "Circuit ID {}",
+                OptionValue::Binary(circuit_id.clone())
+            ));
+        }
+
+        if let Some(remote_id) = &self.remote_id {
+            keys.push(format!(
+                "Remote ID {}",
+                OptionValue::Binary(remote_id.clone())
+            ));
+        }
+
+        write!(f, "{}", keys.join(", "))
+    }
+}
+
+impl std::fmt::Display for LeaseStoreType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LeaseStoreType::Memory => "Memory",
+                LeaseStoreType::File => "File",
+                #[cfg(feature = "sqlite")]
+                LeaseStoreType::SQLite => "SQLite",
+            }
+        )
     }
 }
 
@@ -304,21 +969,55 @@ impl std::fmt::Display for ConfigurationError {
                     format!("Unable to load configuration file - {}", error),
                 ConfigurationError::InvalidIP(str) => format!("Invalid I.P. address ({})", str),
                 ConfigurationError::InvalidMAC(str) => format!("Invalid MAC address ({})", str),
+                ConfigurationError::InvalidHex(key, value) =>
+                    format!("Invalid value for {} \"{}\", expected hex", key, value),
+                ConfigurationError::InvalidClientID(str) => format!("Invalid client ID ({})", str),
                 ConfigurationError::InvalidTime(str) => format!("Invalid time ({})", str),
                 ConfigurationError::InvalidLogLimit(str, error) =>
                     format!("Invalid log limit \"{}\" ({})", str, error),
-                ConfigurationError::NoLeaseStartIP => format!("No lease start I.P. address"),
-                ConfigurationError::NoLeaseEndIP => format!("No lease end I.P. address"),
-                ConfigurationError::NoGatewayIP => format!("No gateway I.P. address"),
-                ConfigurationError::NoOurIP => format!("Our I.P. address not specified"),
-                ConfigurationError::NoSubnetMask => format!("No subnet mask"),
-                ConfigurationError::NoBroadcastAddress => format!("No broadcast address"),
-                ConfigurationError::NoDNS => format!("No DNS specified"),
-                ConfigurationError::NoAlternativeDNS => format!("No alternative DNS specified"),
-                ConfigurationError::NoReservedIP(address) => format!(
-                    "Reserved MAC Address has no match I.P. address ({})",
-                    address
+                ConfigurationError::InvalidLeaseStore(str) =>
+                    format!("Invalid lease store \"{}\"", str),
+                ConfigurationError::InvalidBoolean(key, value) => format!(
+                    "Invalid value for {} \"{}\", expected true or false",
+                    key, value
+                ),
+                ConfigurationError::BootFileTooLong(file) => format!(
+                    "Boot file \"{}\" is longer than {} bytes",
+                    file, MAX_BOOT_FILE_LENGTH
+                ),
+                ConfigurationError::NoLeaseStartIP => "No lease start I.P. address".to_owned(),
+                ConfigurationError::NoLeaseEndIP => "No lease end I.P. address".to_owned(),
+                ConfigurationError::NoGatewayIP => "No gateway I.P. address".to_owned(),
+                ConfigurationError::NoOurIP => "Our I.P. address not specified".to_owned(),
+                ConfigurationError::NoSubnetMask => "No subnet mask".to_owned(),
+                ConfigurationError::NoBroadcastAddress => "No broadcast address".to_owned(),
+                ConfigurationError::NoDNS => "No DNS specified".to_owned(),
+                ConfigurationError::NoAlternativeDNS => "No alternative DNS specified".to_owned(),
+                ConfigurationError::NoReservedIP(index) =>
+                    format!("Reservation {} has no I.P. address", index),
+                ConfigurationError::NoReservationKey(index) => format!(
+                    "Reservation {} has no client ID, MAC address, circuit ID or remote ID",
+                    index
+                ),
+                ConfigurationError::InvalidExcludedRange(start, end) => format!(
+                    "Excluded range start is after its final address ({} - {})",
+                    start, end
+                ),
+                ConfigurationError::InvalidPoolRange(start, end) => format!(
+                    "Pool start is after its final address ({} - {})",
+                    start, end
+                ),
+                ConfigurationError::PoolOutsideSubnet(start, end, subnet) =>
+                    format!("Pool {} - {} is outside of subnet {}", start, end, subnet),
+                ConfigurationError::OverlappingSubnets(first, second) =>
+                    format!("Subnet {} overlaps subnet {}", second, first),
+                ConfigurationError::MissingKey(key) => format!("Missing {}", key),
+                ConfigurationError::InvalidOptionValue(key, value, descriptor) => format!(
+                    "Invalid value for {} \"{}\", expected {}",
+                    key, value, descriptor
                 ),
+                ConfigurationError::OptionNotConfigurable(key) =>
+                    format!("{} is set by the server and can't be configured", key),
                 ConfigurationError::OpenLogFileError(file, error) =>
                     format!("Unable to open log file \"{}\" ({})", file, error),
             }
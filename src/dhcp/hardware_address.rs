@@ -0,0 +1,59 @@
+use super::HardwareType;
+use crate::MACAddress;
+
+pub const MAX_HARDWARE_ADDRESS_LENGTH: usize = 16;
+
+// A client hardware address of any type, stored as it appears in chaddr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HardwareAddress {
+    hardware_type: HardwareType,
+    length: u8,
+    address: [u8; MAX_HARDWARE_ADDRESS_LENGTH],
+}
+
+impl HardwareAddress {
+    // Addresses longer than chaddr are truncated
+    pub fn new(hardware_type: HardwareType, address: &[u8]) -> Self {
+        let length = address.len().min(MAX_HARDWARE_ADDRESS_LENGTH);
+
+        let mut padded = [0; MAX_HARDWARE_ADDRESS_LENGTH];
+        padded[..length].copy_from_slice(&address[..length]);
+
+        HardwareAddress {
+            hardware_type,
+            length: length as u8,
+            address: padded,
+        }
+    }
+
+    pub fn hardware_type(&self) -> HardwareType {
+        self.hardware_type
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.address[..self.length as usize]
+    }
+
+    // The full chaddr field, padded with zeros
+    pub fn padded(&self) -> &[u8; MAX_HARDWARE_ADDRESS_LENGTH] {
+        &self.address
+    }
+
+    pub fn mac_address(&self) -> Option<MACAddress> {
+        match (self.hardware_type, self.length) {
+            (HardwareType::Ethernet, 6) => Some(MACAddress::new([
+                self.address[0],
+                self.address[1],
+                self.address[2],
+                self.address[3],
+                self.address[4],
+                self.address[5],
+            ])),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,5 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum HardwareType {
     Ethernet,
     ExperimentalEthernet,
@@ -19,6 +20,8 @@ pub enum HardwareType {
     HDLC,
     FibreChannel,
     SerialLine,
+    IEEE1394,
+    InfiniBand,
     Other(u8),
 }
 
@@ -44,6 +47,8 @@ impl HardwareType {
             17 => HardwareType::HDLC,
             18 => HardwareType::FibreChannel,
             20 => HardwareType::SerialLine,
+            24 => HardwareType::IEEE1394,
+            32 => HardwareType::InfiniBand,
             _ => HardwareType::Other(value),
         }
     }
@@ -69,6 +74,8 @@ impl HardwareType {
             HardwareType::HDLC => 17,
             HardwareType::FibreChannel => 18,
             HardwareType::SerialLine => 20,
+            HardwareType::IEEE1394 => 24,
+            HardwareType::InfiniBand => 32,
             HardwareType::Other(value) => *value,
         }
     }
@@ -80,26 +87,28 @@ impl std::fmt::Display for HardwareType {
             f,
             "{}",
             match self {
-                HardwareType::Ethernet => format!("Ethernet (10Mb)"),
-                HardwareType::ExperimentalEthernet => format!("Experimental Ethernet (3Mb)"),
-                HardwareType::AmateurRadioAX25 => format!("Amateur Radio AX.25"),
-                HardwareType::ProteonProNetTokenRing => format!("Proteon ProNET Token Ring"),
-                HardwareType::Chaos => format!("Chaos"),
-                HardwareType::IEEE802Networks => format!("IEEE 802 Networks"),
-                HardwareType::ARCNET => format!("ARCNET"),
-                HardwareType::Hyperchannel => format!("Hyperchannel"),
-                HardwareType::Lanstar => format!("Lanstar"),
-                HardwareType::AutonetShortAddress => format!("Autonet Short Address"),
-                HardwareType::LocalTalk => format!("LocalTalk"),
-                HardwareType::LocalNet => format!("LocalNet (IBM PCNet or SYTEK LocalNET)"),
-                HardwareType::UltraLink => format!("Ultra link"),
-                HardwareType::SMDS => format!("SMDS"),
-                HardwareType::FrameRelay => format!("Frame Relay"),
+                HardwareType::Ethernet => "Ethernet (10Mb)".to_owned(),
+                HardwareType::ExperimentalEthernet => "Experimental Ethernet (3Mb)".to_owned(),
+                HardwareType::AmateurRadioAX25 => "Amateur Radio AX.25".to_owned(),
+                HardwareType::ProteonProNetTokenRing => "Proteon ProNET Token Ring".to_owned(),
+                HardwareType::Chaos => "Chaos".to_owned(),
+                HardwareType::IEEE802Networks => "IEEE 802 Networks".to_owned(),
+                HardwareType::ARCNET => "ARCNET".to_owned(),
+                HardwareType::Hyperchannel => "Hyperchannel".to_owned(),
+                HardwareType::Lanstar => "Lanstar".to_owned(),
+                HardwareType::AutonetShortAddress => "Autonet Short Address".to_owned(),
+                HardwareType::LocalTalk => "LocalTalk".to_owned(),
+                HardwareType::LocalNet => "LocalNet (IBM PCNet or SYTEK LocalNET)".to_owned(),
+                HardwareType::UltraLink => "Ultra link".to_owned(),
+                HardwareType::SMDS => "SMDS".to_owned(),
+                HardwareType::FrameRelay => "Frame Relay".to_owned(),
                 HardwareType::AsynchronousTransmissionMode =>
-                    format!("Asynchronous Transmission Mode (ATM)"),
-                HardwareType::HDLC => format!("HDLC"),
-                HardwareType::FibreChannel => format!("Fibre Channel"),
-                HardwareType::SerialLine => format!("Serial Line"),
+                    "Asynchronous Transmission Mode (ATM)".to_owned(),
+                HardwareType::HDLC => "HDLC".to_owned(),
+                HardwareType::FibreChannel => "Fibre Channel".to_owned(),
+                HardwareType::SerialLine => "Serial Line".to_owned(),
+                HardwareType::IEEE1394 => "IEEE 1394.1995".to_owned(),
+                HardwareType::InfiniBand => "InfiniBand".to_owned(),
                 HardwareType::Other(value) => format!("Other ({})", value),
             }
         )
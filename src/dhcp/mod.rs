@@ -1,9 +1,17 @@
+mod hardware_address;
 mod hardware_type;
 mod message_type;
 mod option;
 mod packet;
+mod relay_agent;
+mod reply;
+mod view;
 
+pub use hardware_address::*;
 pub use hardware_type::*;
 pub use message_type::*;
 pub use option::*;
 pub use packet::*;
+pub use relay_agent::*;
+pub use reply::*;
+pub use view::*;
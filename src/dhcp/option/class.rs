@@ -1,4 +1,7 @@
-#[derive(Clone, Copy, PartialEq, Eq)]
+use super::OptionDescriptor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum DHCPOptionClass {
     Pad,
     SubnetMask,
@@ -42,7 +45,7 @@ pub enum DHCPOptionClass {
     KeepaliveData,
     NISDomain,
     NISServers,
-    NIPServers,
+    NTPServers,
     VendorSpecific,
     NETBIOSNameSrv,
     NETBIOSDistSrv,
@@ -64,6 +67,103 @@ pub enum DHCPOptionClass {
     ClientID,
     NetwareIPDomain,
     NetwareIPOption,
+    NISPlusDomain,
+    NISPlusServers,
+    TFTPServerName,
+    BootFileName,
+    HomeAgentAddresses,
+    SMTPServer,
+    POP3Server,
+    NNTPServer,
+    WWWServer,
+    FingerServer,
+    IRCServer,
+    StreetTalkServer,
+    STDAServer,
+    UserClass,
+    DirectoryAgent,
+    ServiceScope,
+    RapidCommit,
+    ClientFQDN,
+    RelayAgentInformation,
+    ISNS,
+    NDSServers,
+    NDSTreeName,
+    NDSContext,
+    BCMCSControllerDomainName,
+    BCMCSControllerAddress,
+    Authentication,
+    ClientLastTransactionTime,
+    AssociatedIP,
+    ClientSystem,
+    ClientNDI,
+    LDAP,
+    UUIDGUID,
+    UserAuth,
+    GeoConfCivic,
+    PCode,
+    TCode,
+    IPv6OnlyPreferred,
+    DHCP4o6S46SAddr,
+    NetinfoAddress,
+    NetinfoTag,
+    CaptivePortal,
+    AutoConfig,
+    NameServiceSearch,
+    SubnetSelection,
+    DomainSearch,
+    SIPServers,
+    ClasslessStaticRoute,
+    CCC,
+    GeoConf,
+    VIVendorClass,
+    VIVendorSpecific,
+    PXE128,
+    PXE129,
+    PXE130,
+    PXE131,
+    PXE132,
+    PXE133,
+    PXE134,
+    PXE135,
+    PANAAgent,
+    LoSTServer,
+    CAPWAPACAddress,
+    MoSAddress,
+    MoSFQDN,
+    SIPUADomains,
+    ANDSFAddress,
+    SZTPRedirect,
+    GeoLoc,
+    ForceRenewNonceCapable,
+    RDNSSSelection,
+    DOTSReferenceIdentifier,
+    DOTSAddress,
+    TFTPServerAddress,
+    StatusCode,
+    BaseTime,
+    StartTimeOfState,
+    QueryStartTime,
+    QueryEndTime,
+    DHCPState,
+    DataSource,
+    PCPServer,
+    PortParams,
+    MUDURL,
+    DNR,
+    Etherboot,
+    IPTelephone,
+    PacketCable,
+    PXELinuxMagic,
+    ConfigurationFile,
+    PathPrefix,
+    RebootTime,
+    SixRD,
+    AccessDomain,
+    SubnetAllocation,
+    VirtualSubnetSelection,
+    WPAD,
+    PrivateUse(u8),
     Other(u8),
     End,
 }
@@ -113,7 +213,7 @@ impl DHCPOptionClass {
             39 => DHCPOptionClass::KeepaliveData,
             40 => DHCPOptionClass::NISDomain,
             41 => DHCPOptionClass::NISServers,
-            42 => DHCPOptionClass::NIPServers,
+            42 => DHCPOptionClass::NTPServers,
             43 => DHCPOptionClass::VendorSpecific,
             44 => DHCPOptionClass::NETBIOSNameSrv,
             45 => DHCPOptionClass::NETBIOSDistSrv,
@@ -135,7 +235,104 @@ impl DHCPOptionClass {
             61 => DHCPOptionClass::ClientID,
             62 => DHCPOptionClass::NetwareIPDomain,
             63 => DHCPOptionClass::NetwareIPOption,
+            64 => DHCPOptionClass::NISPlusDomain,
+            65 => DHCPOptionClass::NISPlusServers,
+            66 => DHCPOptionClass::TFTPServerName,
+            67 => DHCPOptionClass::BootFileName,
+            68 => DHCPOptionClass::HomeAgentAddresses,
+            69 => DHCPOptionClass::SMTPServer,
+            70 => DHCPOptionClass::POP3Server,
+            71 => DHCPOptionClass::NNTPServer,
+            72 => DHCPOptionClass::WWWServer,
+            73 => DHCPOptionClass::FingerServer,
+            74 => DHCPOptionClass::IRCServer,
+            75 => DHCPOptionClass::StreetTalkServer,
+            76 => DHCPOptionClass::STDAServer,
+            77 => DHCPOptionClass::UserClass,
+            78 => DHCPOptionClass::DirectoryAgent,
+            79 => DHCPOptionClass::ServiceScope,
+            80 => DHCPOptionClass::RapidCommit,
+            81 => DHCPOptionClass::ClientFQDN,
+            82 => DHCPOptionClass::RelayAgentInformation,
+            83 => DHCPOptionClass::ISNS,
+            85 => DHCPOptionClass::NDSServers,
+            86 => DHCPOptionClass::NDSTreeName,
+            87 => DHCPOptionClass::NDSContext,
+            88 => DHCPOptionClass::BCMCSControllerDomainName,
+            89 => DHCPOptionClass::BCMCSControllerAddress,
+            90 => DHCPOptionClass::Authentication,
+            91 => DHCPOptionClass::ClientLastTransactionTime,
+            92 => DHCPOptionClass::AssociatedIP,
+            93 => DHCPOptionClass::ClientSystem,
+            94 => DHCPOptionClass::ClientNDI,
+            95 => DHCPOptionClass::LDAP,
+            97 => DHCPOptionClass::UUIDGUID,
+            98 => DHCPOptionClass::UserAuth,
+            99 => DHCPOptionClass::GeoConfCivic,
+            100 => DHCPOptionClass::PCode,
+            101 => DHCPOptionClass::TCode,
+            108 => DHCPOptionClass::IPv6OnlyPreferred,
+            109 => DHCPOptionClass::DHCP4o6S46SAddr,
+            112 => DHCPOptionClass::NetinfoAddress,
+            113 => DHCPOptionClass::NetinfoTag,
+            114 => DHCPOptionClass::CaptivePortal,
+            116 => DHCPOptionClass::AutoConfig,
+            117 => DHCPOptionClass::NameServiceSearch,
+            118 => DHCPOptionClass::SubnetSelection,
+            119 => DHCPOptionClass::DomainSearch,
+            120 => DHCPOptionClass::SIPServers,
+            121 => DHCPOptionClass::ClasslessStaticRoute,
+            122 => DHCPOptionClass::CCC,
+            123 => DHCPOptionClass::GeoConf,
+            124 => DHCPOptionClass::VIVendorClass,
+            125 => DHCPOptionClass::VIVendorSpecific,
+            128 => DHCPOptionClass::PXE128,
+            129 => DHCPOptionClass::PXE129,
+            130 => DHCPOptionClass::PXE130,
+            131 => DHCPOptionClass::PXE131,
+            132 => DHCPOptionClass::PXE132,
+            133 => DHCPOptionClass::PXE133,
+            134 => DHCPOptionClass::PXE134,
+            135 => DHCPOptionClass::PXE135,
+            136 => DHCPOptionClass::PANAAgent,
+            137 => DHCPOptionClass::LoSTServer,
+            138 => DHCPOptionClass::CAPWAPACAddress,
+            139 => DHCPOptionClass::MoSAddress,
+            140 => DHCPOptionClass::MoSFQDN,
+            141 => DHCPOptionClass::SIPUADomains,
+            142 => DHCPOptionClass::ANDSFAddress,
+            143 => DHCPOptionClass::SZTPRedirect,
+            144 => DHCPOptionClass::GeoLoc,
+            145 => DHCPOptionClass::ForceRenewNonceCapable,
+            146 => DHCPOptionClass::RDNSSSelection,
+            147 => DHCPOptionClass::DOTSReferenceIdentifier,
+            148 => DHCPOptionClass::DOTSAddress,
+            150 => DHCPOptionClass::TFTPServerAddress,
+            151 => DHCPOptionClass::StatusCode,
+            152 => DHCPOptionClass::BaseTime,
+            153 => DHCPOptionClass::StartTimeOfState,
+            154 => DHCPOptionClass::QueryStartTime,
+            155 => DHCPOptionClass::QueryEndTime,
+            156 => DHCPOptionClass::DHCPState,
+            157 => DHCPOptionClass::DataSource,
+            158 => DHCPOptionClass::PCPServer,
+            159 => DHCPOptionClass::PortParams,
+            161 => DHCPOptionClass::MUDURL,
+            162 => DHCPOptionClass::DNR,
+            175 => DHCPOptionClass::Etherboot,
+            176 => DHCPOptionClass::IPTelephone,
+            177 => DHCPOptionClass::PacketCable,
+            208 => DHCPOptionClass::PXELinuxMagic,
+            209 => DHCPOptionClass::ConfigurationFile,
+            210 => DHCPOptionClass::PathPrefix,
+            211 => DHCPOptionClass::RebootTime,
+            212 => DHCPOptionClass::SixRD,
+            213 => DHCPOptionClass::AccessDomain,
+            220 => DHCPOptionClass::SubnetAllocation,
+            221 => DHCPOptionClass::VirtualSubnetSelection,
+            252 => DHCPOptionClass::WPAD,
             255 => DHCPOptionClass::End,
+            224..=254 => DHCPOptionClass::PrivateUse(class),
             _ => DHCPOptionClass::Other(class),
         }
     }
@@ -184,7 +381,7 @@ impl DHCPOptionClass {
             DHCPOptionClass::KeepaliveData => 39,
             DHCPOptionClass::NISDomain => 40,
             DHCPOptionClass::NISServers => 41,
-            DHCPOptionClass::NIPServers => 42,
+            DHCPOptionClass::NTPServers => 42,
             DHCPOptionClass::VendorSpecific => 43,
             DHCPOptionClass::NETBIOSNameSrv => 44,
             DHCPOptionClass::NETBIOSDistSrv => 45,
@@ -206,85 +403,144 @@ impl DHCPOptionClass {
             DHCPOptionClass::ClientID => 61,
             DHCPOptionClass::NetwareIPDomain => 62,
             DHCPOptionClass::NetwareIPOption => 63,
+            DHCPOptionClass::NISPlusDomain => 64,
+            DHCPOptionClass::NISPlusServers => 65,
+            DHCPOptionClass::TFTPServerName => 66,
+            DHCPOptionClass::BootFileName => 67,
+            DHCPOptionClass::HomeAgentAddresses => 68,
+            DHCPOptionClass::SMTPServer => 69,
+            DHCPOptionClass::POP3Server => 70,
+            DHCPOptionClass::NNTPServer => 71,
+            DHCPOptionClass::WWWServer => 72,
+            DHCPOptionClass::FingerServer => 73,
+            DHCPOptionClass::IRCServer => 74,
+            DHCPOptionClass::StreetTalkServer => 75,
+            DHCPOptionClass::STDAServer => 76,
+            DHCPOptionClass::UserClass => 77,
+            DHCPOptionClass::DirectoryAgent => 78,
+            DHCPOptionClass::ServiceScope => 79,
+            DHCPOptionClass::RapidCommit => 80,
+            DHCPOptionClass::ClientFQDN => 81,
+            DHCPOptionClass::RelayAgentInformation => 82,
+            DHCPOptionClass::ISNS => 83,
+            DHCPOptionClass::NDSServers => 85,
+            DHCPOptionClass::NDSTreeName => 86,
+            DHCPOptionClass::NDSContext => 87,
+            DHCPOptionClass::BCMCSControllerDomainName => 88,
+            DHCPOptionClass::BCMCSControllerAddress => 89,
+            DHCPOptionClass::Authentication => 90,
+            DHCPOptionClass::ClientLastTransactionTime => 91,
+            DHCPOptionClass::AssociatedIP => 92,
+            DHCPOptionClass::ClientSystem => 93,
+            DHCPOptionClass::ClientNDI => 94,
+            DHCPOptionClass::LDAP => 95,
+            DHCPOptionClass::UUIDGUID => 97,
+            DHCPOptionClass::UserAuth => 98,
+            DHCPOptionClass::GeoConfCivic => 99,
+            DHCPOptionClass::PCode => 100,
+            DHCPOptionClass::TCode => 101,
+            DHCPOptionClass::IPv6OnlyPreferred => 108,
+            DHCPOptionClass::DHCP4o6S46SAddr => 109,
+            DHCPOptionClass::NetinfoAddress => 112,
+            DHCPOptionClass::NetinfoTag => 113,
+            DHCPOptionClass::CaptivePortal => 114,
+            DHCPOptionClass::AutoConfig => 116,
+            DHCPOptionClass::NameServiceSearch => 117,
+            DHCPOptionClass::SubnetSelection => 118,
+            DHCPOptionClass::DomainSearch => 119,
+            DHCPOptionClass::SIPServers => 120,
+            DHCPOptionClass::ClasslessStaticRoute => 121,
+            DHCPOptionClass::CCC => 122,
+            DHCPOptionClass::GeoConf => 123,
+            DHCPOptionClass::VIVendorClass => 124,
+            DHCPOptionClass::VIVendorSpecific => 125,
+            DHCPOptionClass::PXE128 => 128,
+            DHCPOptionClass::PXE129 => 129,
+            DHCPOptionClass::PXE130 => 130,
+            DHCPOptionClass::PXE131 => 131,
+            DHCPOptionClass::PXE132 => 132,
+            DHCPOptionClass::PXE133 => 133,
+            DHCPOptionClass::PXE134 => 134,
+            DHCPOptionClass::PXE135 => 135,
+            DHCPOptionClass::PANAAgent => 136,
+            DHCPOptionClass::LoSTServer => 137,
+            DHCPOptionClass::CAPWAPACAddress => 138,
+            DHCPOptionClass::MoSAddress => 139,
+            DHCPOptionClass::MoSFQDN => 140,
+            DHCPOptionClass::SIPUADomains => 141,
+            DHCPOptionClass::ANDSFAddress => 142,
+            DHCPOptionClass::SZTPRedirect => 143,
+            DHCPOptionClass::GeoLoc => 144,
+            DHCPOptionClass::ForceRenewNonceCapable => 145,
+            DHCPOptionClass::RDNSSSelection => 146,
+            DHCPOptionClass::DOTSReferenceIdentifier => 147,
+            DHCPOptionClass::DOTSAddress => 148,
+            DHCPOptionClass::TFTPServerAddress => 150,
+            DHCPOptionClass::StatusCode => 151,
+            DHCPOptionClass::BaseTime => 152,
+            DHCPOptionClass::StartTimeOfState => 153,
+            DHCPOptionClass::QueryStartTime => 154,
+            DHCPOptionClass::QueryEndTime => 155,
+            DHCPOptionClass::DHCPState => 156,
+            DHCPOptionClass::DataSource => 157,
+            DHCPOptionClass::PCPServer => 158,
+            DHCPOptionClass::PortParams => 159,
+            DHCPOptionClass::MUDURL => 161,
+            DHCPOptionClass::DNR => 162,
+            DHCPOptionClass::Etherboot => 175,
+            DHCPOptionClass::IPTelephone => 176,
+            DHCPOptionClass::PacketCable => 177,
+            DHCPOptionClass::PXELinuxMagic => 208,
+            DHCPOptionClass::ConfigurationFile => 209,
+            DHCPOptionClass::PathPrefix => 210,
+            DHCPOptionClass::RebootTime => 211,
+            DHCPOptionClass::SixRD => 212,
+            DHCPOptionClass::AccessDomain => 213,
+            DHCPOptionClass::SubnetAllocation => 220,
+            DHCPOptionClass::VirtualSubnetSelection => 221,
+            DHCPOptionClass::WPAD => 252,
             DHCPOptionClass::End => 255,
+            DHCPOptionClass::PrivateUse(class) => *class,
             DHCPOptionClass::Other(class) => *class,
         }
     }
+
+    pub fn descriptor(&self) -> &'static OptionDescriptor {
+        match OptionDescriptor::lookup(self.generate()) {
+            Some(descriptor) => descriptor,
+            None => OptionDescriptor::unknown(),
+        }
+    }
+
+    // Options the server fills in itself which can't be set in the configuration
+    pub fn is_configurable(&self) -> bool {
+        !matches!(
+            self,
+            DHCPOptionClass::Pad
+                | DHCPOptionClass::End
+                | DHCPOptionClass::AddressRequest
+                | DHCPOptionClass::AddressTime
+                | DHCPOptionClass::Overload
+                | DHCPOptionClass::DHCPMsgType
+                | DHCPOptionClass::DHCPServerID
+                | DHCPOptionClass::ParameterList
+                | DHCPOptionClass::DHCPMessage
+                | DHCPOptionClass::DHCPMaxMsgSize
+                | DHCPOptionClass::RenewalTime
+                | DHCPOptionClass::RebindingTime
+                | DHCPOptionClass::ClientID
+                | DHCPOptionClass::RelayAgentInformation
+                | DHCPOptionClass::SubnetSelection
+        )
+    }
 }
 
 impl std::fmt::Display for DHCPOptionClass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                DHCPOptionClass::Pad => format!("Pad"),
-                DHCPOptionClass::SubnetMask => format!("Subnet Mask"),
-                DHCPOptionClass::TimeOffset => format!("Time Offset"),
-                DHCPOptionClass::Gateways => format!("Gateways"),
-                DHCPOptionClass::TimeServer => format!("Time Server"),
-                DHCPOptionClass::NameServer => format!("Name Server"),
-                DHCPOptionClass::DomainServer => format!("Domain Server"),
-                DHCPOptionClass::LogServer => format!("Log Server"),
-                DHCPOptionClass::QuotesServer => format!("Quotes Server"),
-                DHCPOptionClass::LPRServer => format!("LPR Server"),
-                DHCPOptionClass::ImpressServer => format!("Impress Server"),
-                DHCPOptionClass::RLPServer => format!("RLP Server"),
-                DHCPOptionClass::Hostname => format!("Hostname"),
-                DHCPOptionClass::BootFileSize => format!("Boot File Size"),
-                DHCPOptionClass::MeritDumpFile => format!("Merit Dump File"),
-                DHCPOptionClass::DomainName => format!("Domain Name"),
-                DHCPOptionClass::SwapServer => format!("Swap Server"),
-                DHCPOptionClass::RootPath => format!("Root Path"),
-                DHCPOptionClass::ExtensionFile => format!("Extension File"),
-                DHCPOptionClass::ForwardOnOff => format!("Forward On/Off"),
-                DHCPOptionClass::SrcRteOnOff => format!("Src Rte On/Off"),
-                DHCPOptionClass::PolicyFilter => format!("Policy Filter"),
-                DHCPOptionClass::MaxDGAssembly => format!("Max DG Assembly"),
-                DHCPOptionClass::DefaultIPTTL => format!("Default IP TTL"),
-                DHCPOptionClass::MTUTimeout => format!("MTU Timeout"),
-                DHCPOptionClass::MTUPlateau => format!("MTU Plateau"),
-                DHCPOptionClass::MTUInterface => format!("MTU Interface"),
-                DHCPOptionClass::MTUSubnet => format!("MTU Subnet"),
-                DHCPOptionClass::BroadcastAddress => format!("Broadcast Address"),
-                DHCPOptionClass::MaskDiscovery => format!("Mask Discovery"),
-                DHCPOptionClass::MaskSupplier => format!("Mask Supplier"),
-                DHCPOptionClass::RouterDiscovery => format!("Router Discovery"),
-                DHCPOptionClass::RouterRequest => format!("Router Request"),
-                DHCPOptionClass::StaticRoute => format!("Static Route"),
-                DHCPOptionClass::Trailers => format!("Trailers"),
-                DHCPOptionClass::ARPTimeout => format!("ARP Timeout"),
-                DHCPOptionClass::Ethernet => format!("Ethernet"),
-                DHCPOptionClass::DefaultTCPTTL => format!("Default TCP TTL"),
-                DHCPOptionClass::KeepaliveTime => format!("Keepalive Time"),
-                DHCPOptionClass::KeepaliveData => format!("Keepalive Data"),
-                DHCPOptionClass::NISDomain => format!("NIS Domain"),
-                DHCPOptionClass::NISServers => format!("NIS Servers"),
-                DHCPOptionClass::NIPServers => format!("NIP Servers"),
-                DHCPOptionClass::VendorSpecific => format!("Vendor Specific"),
-                DHCPOptionClass::NETBIOSNameSrv => format!("NET BIOS Name Srv"),
-                DHCPOptionClass::NETBIOSDistSrv => format!("NET BIOS Dist Srv"),
-                DHCPOptionClass::NETBIOSNoteType => format!("NET BIOS Note Type"),
-                DHCPOptionClass::NETBIOSScope => format!("NET BIOS Scope"),
-                DHCPOptionClass::XWindowFont => format!("X Window Font"),
-                DHCPOptionClass::XWindowManager => format!("X Window Manager"),
-                DHCPOptionClass::AddressRequest => format!("Address Request"),
-                DHCPOptionClass::AddressTime => format!("Address Time"),
-                DHCPOptionClass::Overload => format!("Overload"),
-                DHCPOptionClass::DHCPMsgType => format!("DHCP Msg Type"),
-                DHCPOptionClass::DHCPServerID => format!("DHCP Server ID"),
-                DHCPOptionClass::ParameterList => format!("Parameter List"),
-                DHCPOptionClass::DHCPMessage => format!("DHCP Message"),
-                DHCPOptionClass::DHCPMaxMsgSize => format!("DHCP Max Msg Size"),
-                DHCPOptionClass::RenewalTime => format!("Renewal Time"),
-                DHCPOptionClass::RebindingTime => format!("Rebinding Time"),
-                DHCPOptionClass::ClassID => format!("Class ID"),
-                DHCPOptionClass::ClientID => format!("Client ID"),
-                DHCPOptionClass::NetwareIPDomain => format!("Netware IP Domain"),
-                DHCPOptionClass::NetwareIPOption => format!("Netware IP Option"),
-                DHCPOptionClass::Other(value) => format!("Other ({})", value),
-                DHCPOptionClass::End => format!("End"),
-            }
-        )
+        match self {
+            DHCPOptionClass::PrivateUse(value) => write!(f, "Private Use ({})", value),
+            DHCPOptionClass::Other(value) => write!(f, "Other ({})", value),
+            _ => write!(f, "{}", self.descriptor().name()),
+        }
     }
 }
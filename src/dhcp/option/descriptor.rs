@@ -0,0 +1,303 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionDataType {
+    IPAddress,
+    IPAddressPair,
+    String,
+    DomainName,
+    U8,
+    U16,
+    U32,
+    I32,
+    Bool,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplicity {
+    Single,
+    List,
+}
+
+#[derive(Debug)]
+pub struct OptionDescriptor {
+    code: u8,
+    name: &'static str,
+    data_type: OptionDataType,
+    multiplicity: Multiplicity,
+    min_length: usize,
+    max_length: Option<usize>,
+}
+
+use Multiplicity::{List, Single};
+use OptionDataType::*;
+
+// Descriptors for every known option, sorted by code
+static DESCRIPTORS: &[OptionDescriptor] = &[
+    OptionDescriptor::new(0, "Pad", Binary, Single, 0, Some(0)),
+    OptionDescriptor::new(1, "Subnet Mask", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(2, "Time Offset", I32, Single, 4, Some(4)),
+    OptionDescriptor::new(3, "Gateways", IPAddress, List, 4, None),
+    OptionDescriptor::new(4, "Time Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(5, "Name Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(6, "Domain Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(7, "Log Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(8, "Quotes Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(9, "LPR Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(10, "Impress Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(11, "RLP Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(12, "Hostname", String, Single, 1, None),
+    OptionDescriptor::new(13, "Boot File Size", U16, Single, 2, Some(2)),
+    OptionDescriptor::new(14, "Merit Dump File", String, Single, 1, None),
+    OptionDescriptor::new(15, "Domain Name", String, Single, 1, None),
+    OptionDescriptor::new(16, "Swap Server", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(17, "Root Path", String, Single, 1, None),
+    OptionDescriptor::new(18, "Extension File", String, Single, 1, None),
+    OptionDescriptor::new(19, "Forward On/Off", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(20, "Src Rte On/Off", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(21, "Policy Filter", IPAddressPair, List, 8, None),
+    OptionDescriptor::new(22, "Max DG Assembly", U16, Single, 2, Some(2)),
+    OptionDescriptor::new(23, "Default IP TTL", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(24, "MTU Timeout", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(25, "MTU Plateau", U16, List, 2, None),
+    OptionDescriptor::new(26, "MTU Interface", U16, Single, 2, Some(2)),
+    OptionDescriptor::new(27, "MTU Subnet", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(28, "Broadcast Address", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(29, "Mask Discovery", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(30, "Mask Supplier", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(31, "Router Discovery", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(32, "Router Request", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(33, "Static Route", IPAddressPair, List, 8, None),
+    OptionDescriptor::new(34, "Trailers", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(35, "ARP Timeout", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(36, "Ethernet", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(37, "Default TCP TTL", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(38, "Keepalive Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(39, "Keepalive Data", Bool, Single, 1, Some(1)),
+    OptionDescriptor::new(40, "NIS Domain", String, Single, 1, None),
+    OptionDescriptor::new(41, "NIS Servers", IPAddress, List, 4, None),
+    OptionDescriptor::new(42, "NTP Servers", IPAddress, List, 4, None),
+    OptionDescriptor::new(43, "Vendor Specific", Binary, Single, 1, None),
+    OptionDescriptor::new(44, "NET BIOS Name Srv", IPAddress, List, 4, None),
+    OptionDescriptor::new(45, "NET BIOS Dist Srv", IPAddress, List, 4, None),
+    OptionDescriptor::new(46, "NET BIOS Note Type", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(47, "NET BIOS Scope", String, Single, 1, None),
+    OptionDescriptor::new(48, "X Window Font", IPAddress, List, 4, None),
+    OptionDescriptor::new(49, "X Window Manager", IPAddress, List, 4, None),
+    OptionDescriptor::new(50, "Address Request", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(51, "Address Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(52, "Overload", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(53, "DHCP Msg Type", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(54, "DHCP Server ID", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(55, "Parameter List", U8, List, 1, None),
+    OptionDescriptor::new(56, "DHCP Message", String, Single, 1, None),
+    OptionDescriptor::new(57, "DHCP Max Msg Size", U16, Single, 2, Some(2)),
+    OptionDescriptor::new(58, "Renewal Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(59, "Rebinding Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(60, "Class ID", String, Single, 1, None),
+    OptionDescriptor::new(61, "Client ID", Binary, Single, 2, None),
+    OptionDescriptor::new(62, "Netware IP Domain", String, Single, 1, None),
+    OptionDescriptor::new(63, "Netware IP Option", Binary, Single, 1, None),
+    OptionDescriptor::new(64, "NIS+ Domain", String, Single, 1, None),
+    OptionDescriptor::new(65, "NIS+ Servers", IPAddress, List, 4, None),
+    OptionDescriptor::new(66, "TFTP Server Name", String, Single, 1, None),
+    OptionDescriptor::new(67, "Boot File Name", String, Single, 1, None),
+    OptionDescriptor::new(68, "Home Agent Addresses", IPAddress, List, 0, None),
+    OptionDescriptor::new(69, "SMTP Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(70, "POP3 Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(71, "NNTP Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(72, "WWW Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(73, "Finger Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(74, "IRC Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(75, "StreetTalk Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(76, "STDA Server", IPAddress, List, 4, None),
+    OptionDescriptor::new(77, "User Class", Binary, Single, 1, None),
+    OptionDescriptor::new(78, "Directory Agent", Binary, Single, 1, None),
+    OptionDescriptor::new(79, "Service Scope", Binary, Single, 1, None),
+    OptionDescriptor::new(80, "Rapid Commit", Binary, Single, 0, Some(0)),
+    OptionDescriptor::new(81, "Client FQDN", Binary, Single, 3, None),
+    OptionDescriptor::new(82, "Relay Agent Information", Binary, Single, 2, None),
+    OptionDescriptor::new(83, "iSNS", Binary, Single, 14, None),
+    OptionDescriptor::new(85, "NDS Servers", IPAddress, List, 4, None),
+    OptionDescriptor::new(86, "NDS Tree Name", String, Single, 1, None),
+    OptionDescriptor::new(87, "NDS Context", String, Single, 1, None),
+    OptionDescriptor::new(
+        88,
+        "BCMCS Controller Domain Name",
+        DomainName,
+        List,
+        1,
+        None,
+    ),
+    OptionDescriptor::new(89, "BCMCS Controller Address", IPAddress, List, 4, None),
+    OptionDescriptor::new(90, "Authentication", Binary, Single, 11, None),
+    OptionDescriptor::new(91, "Client Last Transaction Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(92, "Associated IP", IPAddress, List, 4, None),
+    OptionDescriptor::new(93, "Client System", U16, List, 2, None),
+    OptionDescriptor::new(94, "Client NDI", Binary, Single, 3, Some(3)),
+    OptionDescriptor::new(95, "LDAP", String, Single, 1, None),
+    OptionDescriptor::new(97, "UUID/GUID", Binary, Single, 1, None),
+    OptionDescriptor::new(98, "User Auth", String, Single, 1, None),
+    OptionDescriptor::new(99, "GeoConf Civic", Binary, Single, 3, None),
+    OptionDescriptor::new(100, "PCode", String, Single, 1, None),
+    OptionDescriptor::new(101, "TCode", String, Single, 1, None),
+    OptionDescriptor::new(108, "IPv6 Only Preferred", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(
+        109,
+        "DHCP 4o6 S46 Source Address",
+        Binary,
+        Single,
+        16,
+        Some(16),
+    ),
+    OptionDescriptor::new(112, "Netinfo Address", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(113, "Netinfo Tag", String, Single, 1, None),
+    OptionDescriptor::new(114, "Captive Portal", String, Single, 1, None),
+    OptionDescriptor::new(116, "Auto Config", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(117, "Name Service Search", U16, List, 2, None),
+    OptionDescriptor::new(118, "Subnet Selection", IPAddress, Single, 4, Some(4)),
+    OptionDescriptor::new(119, "Domain Search", DomainName, List, 1, None),
+    OptionDescriptor::new(120, "SIP Servers", Binary, Single, 1, None),
+    OptionDescriptor::new(121, "Classless Static Route", Binary, Single, 5, None),
+    OptionDescriptor::new(122, "CCC", Binary, Single, 1, None),
+    OptionDescriptor::new(123, "GeoConf", Binary, Single, 16, Some(16)),
+    OptionDescriptor::new(124, "V-I Vendor Class", Binary, Single, 5, None),
+    OptionDescriptor::new(125, "V-I Vendor Specific", Binary, Single, 5, None),
+    OptionDescriptor::new(128, "PXE Option 128", Binary, Single, 1, None),
+    OptionDescriptor::new(129, "PXE Option 129", Binary, Single, 1, None),
+    OptionDescriptor::new(130, "PXE Option 130", Binary, Single, 1, None),
+    OptionDescriptor::new(131, "PXE Option 131", Binary, Single, 1, None),
+    OptionDescriptor::new(132, "PXE Option 132", Binary, Single, 1, None),
+    OptionDescriptor::new(133, "PXE Option 133", Binary, Single, 1, None),
+    OptionDescriptor::new(134, "PXE Option 134", Binary, Single, 1, None),
+    OptionDescriptor::new(135, "PXE Option 135", Binary, Single, 1, None),
+    OptionDescriptor::new(136, "PANA Agent", IPAddress, List, 4, None),
+    OptionDescriptor::new(137, "LoST Server", DomainName, Single, 1, None),
+    OptionDescriptor::new(138, "CAPWAP AC Address", IPAddress, List, 4, None),
+    OptionDescriptor::new(139, "MoS Address", Binary, Single, 1, None),
+    OptionDescriptor::new(140, "MoS FQDN", Binary, Single, 1, None),
+    OptionDescriptor::new(141, "SIP UA Domains", DomainName, List, 1, None),
+    OptionDescriptor::new(142, "ANDSF Address", IPAddress, List, 4, None),
+    OptionDescriptor::new(143, "SZTP Redirect", Binary, Single, 1, None),
+    OptionDescriptor::new(144, "GeoLoc", Binary, Single, 16, Some(16)),
+    OptionDescriptor::new(145, "Force Renew Nonce Capable", U8, List, 1, None),
+    OptionDescriptor::new(146, "RDNSS Selection", Binary, Single, 1, None),
+    OptionDescriptor::new(
+        147,
+        "DOTS Reference Identifier",
+        DomainName,
+        Single,
+        1,
+        None,
+    ),
+    OptionDescriptor::new(148, "DOTS Address", IPAddress, List, 4, None),
+    OptionDescriptor::new(150, "TFTP Server Address", IPAddress, List, 4, None),
+    OptionDescriptor::new(151, "Status Code", Binary, Single, 1, None),
+    OptionDescriptor::new(152, "Base Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(153, "Start Time Of State", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(154, "Query Start Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(155, "Query End Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(156, "DHCP State", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(157, "Data Source", U8, Single, 1, Some(1)),
+    OptionDescriptor::new(158, "PCP Server", Binary, Single, 5, None),
+    OptionDescriptor::new(159, "Port Params", Binary, Single, 4, Some(4)),
+    OptionDescriptor::new(161, "MUD URL", String, Single, 1, None),
+    OptionDescriptor::new(162, "DNR", Binary, Single, 1, None),
+    OptionDescriptor::new(175, "Etherboot", Binary, Single, 1, None),
+    OptionDescriptor::new(176, "IP Telephone", Binary, Single, 1, None),
+    OptionDescriptor::new(177, "PacketCable", Binary, Single, 1, None),
+    OptionDescriptor::new(208, "PXELINUX Magic", Binary, Single, 4, Some(4)),
+    OptionDescriptor::new(209, "Configuration File", String, Single, 1, None),
+    OptionDescriptor::new(210, "Path Prefix", String, Single, 1, None),
+    OptionDescriptor::new(211, "Reboot Time", U32, Single, 4, Some(4)),
+    OptionDescriptor::new(212, "6RD", Binary, Single, 22, None),
+    OptionDescriptor::new(213, "Access Domain", DomainName, Single, 1, None),
+    OptionDescriptor::new(220, "Subnet Allocation", Binary, Single, 1, None),
+    OptionDescriptor::new(221, "Virtual Subnet Selection", Binary, Single, 1, None),
+    OptionDescriptor::new(252, "WPAD", String, Single, 1, None),
+    OptionDescriptor::new(255, "End", Binary, Single, 0, Some(0)),
+];
+
+// Used for options which are not in the table
+static UNKNOWN_DESCRIPTOR: OptionDescriptor =
+    OptionDescriptor::new(0, "Unknown", Binary, Single, 0, None);
+
+impl OptionDescriptor {
+    const fn new(
+        code: u8,
+        name: &'static str,
+        data_type: OptionDataType,
+        multiplicity: Multiplicity,
+        min_length: usize,
+        max_length: Option<usize>,
+    ) -> Self {
+        OptionDescriptor {
+            code,
+            name,
+            data_type,
+            multiplicity,
+            min_length,
+            max_length,
+        }
+    }
+
+    pub fn lookup(code: u8) -> Option<&'static OptionDescriptor> {
+        match DESCRIPTORS.binary_search_by_key(&code, |descriptor| descriptor.code) {
+            Ok(index) => Some(&DESCRIPTORS[index]),
+            Err(_) => None,
+        }
+    }
+
+    pub fn unknown() -> &'static OptionDescriptor {
+        &UNKNOWN_DESCRIPTOR
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn data_type(&self) -> OptionDataType {
+        self.data_type
+    }
+
+    pub fn multiplicity(&self) -> Multiplicity {
+        self.multiplicity
+    }
+
+    pub fn min_length(&self) -> usize {
+        self.min_length
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+}
+
+impl std::fmt::Display for OptionDataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OptionDataType::IPAddress => "I.P. address",
+                OptionDataType::IPAddressPair => "I.P. address pair",
+                OptionDataType::String => "string",
+                OptionDataType::DomainName => "domain name",
+                OptionDataType::U8 => "8-bit integer",
+                OptionDataType::U16 => "16-bit integer",
+                OptionDataType::U32 => "32-bit integer",
+                OptionDataType::I32 => "signed 32-bit integer",
+                OptionDataType::Bool => "boolean",
+                OptionDataType::Binary => "hex string",
+            }
+        )
+    }
+}
+
+impl std::fmt::Display for OptionDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.multiplicity {
+            Multiplicity::Single => write!(f, "{}", self.data_type),
+            Multiplicity::List => write!(f, "{} list", self.data_type),
+        }
+    }
+}
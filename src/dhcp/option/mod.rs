@@ -1,6 +1,10 @@
 mod class;
+mod descriptor;
+mod value;
 
 pub use class::*;
+pub use descriptor::*;
+pub use value::*;
 
 pub struct DHCPOption {
     class: DHCPOptionClass,
@@ -9,7 +13,6 @@ pub struct DHCPOption {
 
 #[derive(Debug)]
 pub enum ParseOptionError {
-    MissingClass,
     MissingLength,
     InvalidLength,
 }
@@ -19,36 +22,6 @@ impl DHCPOption {
         DHCPOption { class, value }
     }
 
-    pub fn parse(slice: &[u8]) -> Result<Self, ParseOptionError> {
-        if slice.len() == 0 {
-            return Err(ParseOptionError::MissingClass);
-        }
-
-        let class = DHCPOptionClass::parse(slice[0]);
-
-        if class == DHCPOptionClass::End {
-            return Ok(DHCPOption {
-                class,
-                value: Vec::new(),
-            });
-        } else if slice.len() == 1 {
-            return Err(ParseOptionError::MissingLength);
-        }
-
-        let length = slice[1] as usize;
-
-        let mut value = Vec::new();
-        for i in 0..length {
-            if i + 2 >= slice.len() {
-                return Err(ParseOptionError::InvalidLength);
-            }
-
-            value.push(slice[i + 2]);
-        }
-
-        Ok(DHCPOption { class, value })
-    }
-
     pub fn class(&self) -> DHCPOptionClass {
         self.class
     }
@@ -57,26 +30,17 @@ impl DHCPOption {
         self.value.as_slice()
     }
 
-    pub fn generate(&self) -> Vec<u8> {
-        let mut vec = vec![self.class.generate()];
-
-        if self.class == DHCPOptionClass::End {
-            return vec;
-        }
-
-        vec.push(self.value.len() as u8);
-
-        for value in &self.value {
-            vec.push(*value);
-        }
-
-        vec
+    pub fn decode(&self) -> Result<OptionValue, OptionValueError> {
+        OptionValue::decode(self.class.descriptor(), &self.value)
     }
 }
 
 impl std::fmt::Display for DHCPOption {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} - {:?}", self.class, self.value)
+        match self.decode() {
+            Ok(value) => write!(f, "{} - {}", self.class, value),
+            Err(_) => write!(f, "{} - {:?} (malformed)", self.class, self.value),
+        }
     }
 }
 
@@ -88,7 +52,6 @@ impl std::fmt::Display for ParseOptionError {
             f,
             "{}",
             match self {
-                ParseOptionError::MissingClass => "Missing class",
                 ParseOptionError::MissingLength => "Missing length",
                 ParseOptionError::InvalidLength => "Invalid length",
             }
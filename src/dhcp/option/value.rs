@@ -0,0 +1,548 @@
+use super::{Multiplicity, OptionDataType, OptionDescriptor};
+use crate::IPAddress;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    IPAddress(Vec<IPAddress>),
+    IPAddressPair(Vec<(IPAddress, IPAddress)>),
+    String(String),
+    DomainName(Vec<String>),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    I32(Vec<i32>),
+    Bool(Vec<bool>),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum OptionValueError {
+    TooShort(usize, usize),
+    TooLong(usize, usize),
+    InvalidLength(usize, OptionDataType),
+    InvalidBool(u8),
+    InvalidDomainName,
+}
+
+// Longest chain of compression pointers followed while decoding a domain name
+const MAX_DOMAIN_POINTERS: usize = 16;
+
+impl OptionValue {
+    #[allow(clippy::manual_is_multiple_of)] // is_multiple_of needs Rust 1.87
+    pub fn decode(descriptor: &OptionDescriptor, bytes: &[u8]) -> Result<Self, OptionValueError> {
+        if bytes.len() < descriptor.min_length() {
+            return Err(OptionValueError::TooShort(
+                bytes.len(),
+                descriptor.min_length(),
+            ));
+        }
+
+        if let Some(max_length) = descriptor.max_length() {
+            if bytes.len() > max_length {
+                return Err(OptionValueError::TooLong(bytes.len(), max_length));
+            }
+        }
+
+        let element_length = match descriptor.data_type() {
+            OptionDataType::IPAddress => 4,
+            OptionDataType::IPAddressPair => 8,
+            OptionDataType::U16 => 2,
+            OptionDataType::U32 | OptionDataType::I32 => 4,
+            _ => 1,
+        };
+
+        if bytes.len() % element_length != 0 {
+            return Err(OptionValueError::InvalidLength(
+                bytes.len(),
+                descriptor.data_type(),
+            ));
+        }
+
+        let elements = bytes.chunks_exact(element_length);
+        Ok(match descriptor.data_type() {
+            OptionDataType::IPAddress => OptionValue::IPAddress(
+                elements
+                    .map(|chunk| IPAddress::new([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect(),
+            ),
+            OptionDataType::IPAddressPair => OptionValue::IPAddressPair(
+                elements
+                    .map(|chunk| {
+                        (
+                            IPAddress::new([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                            IPAddress::new([chunk[4], chunk[5], chunk[6], chunk[7]]),
+                        )
+                    })
+                    .collect(),
+            ),
+            OptionDataType::String => {
+                // Some clients include a terminating null
+                let bytes = match bytes.iter().position(|byte| *byte == 0) {
+                    Some(end) => &bytes[..end],
+                    None => bytes,
+                };
+                OptionValue::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            OptionDataType::DomainName => {
+                let mut domains = Vec::new();
+                let mut offset = 0;
+                while offset < bytes.len() {
+                    let (domain, next) = match decode_domain_name(bytes, offset) {
+                        Some(domain) => domain,
+                        None => return Err(OptionValueError::InvalidDomainName),
+                    };
+
+                    domains.push(domain);
+                    offset = next;
+                }
+                OptionValue::DomainName(domains)
+            }
+            OptionDataType::U8 => OptionValue::U8(bytes.to_vec()),
+            OptionDataType::U16 => OptionValue::U16(elements.map(crate::slice_to_u16).collect()),
+            OptionDataType::U32 => OptionValue::U32(elements.map(crate::slice_to_u32).collect()),
+            OptionDataType::I32 => OptionValue::I32(
+                elements
+                    .map(|chunk| crate::slice_to_u32(chunk) as i32)
+                    .collect(),
+            ),
+            OptionDataType::Bool => {
+                let mut values = Vec::new();
+                for byte in bytes {
+                    values.push(match byte {
+                        0 => false,
+                        1 => true,
+                        _ => return Err(OptionValueError::InvalidBool(*byte)),
+                    });
+                }
+                OptionValue::Bool(values)
+            }
+            OptionDataType::Binary => OptionValue::Binary(bytes.to_vec()),
+        })
+    }
+
+    // Parses a configuration value, lists are separated by commas
+    pub fn parse(descriptor: &OptionDescriptor, value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        let items: Vec<&str> = match descriptor.multiplicity() {
+            Multiplicity::Single => vec![value],
+            Multiplicity::List => value.split(',').map(|item| item.trim()).collect(),
+        };
+
+        let value = match descriptor.data_type() {
+            OptionDataType::IPAddress => {
+                let mut addresses = Vec::new();
+                for item in items {
+                    addresses.push(IPAddress::parse(item)?);
+                }
+                OptionValue::IPAddress(addresses)
+            }
+            OptionDataType::IPAddressPair => {
+                let mut pairs = Vec::new();
+                for item in items {
+                    let parts: Vec<&str> = item.split_whitespace().collect();
+                    if parts.len() != 2 {
+                        return None;
+                    }
+
+                    pairs.push((IPAddress::parse(parts[0])?, IPAddress::parse(parts[1])?));
+                }
+                OptionValue::IPAddressPair(pairs)
+            }
+            OptionDataType::String => OptionValue::String(value.to_owned()),
+            OptionDataType::DomainName => {
+                let mut domains = Vec::new();
+                for item in items {
+                    let domain = item.trim_end_matches('.');
+                    for label in domain.split('.') {
+                        if label.is_empty() || label.len() > 63 {
+                            return None;
+                        }
+                    }
+
+                    domains.push(domain.to_owned());
+                }
+                OptionValue::DomainName(domains)
+            }
+            OptionDataType::U8 => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(item.parse().ok()?);
+                }
+                OptionValue::U8(values)
+            }
+            OptionDataType::U16 => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(item.parse().ok()?);
+                }
+                OptionValue::U16(values)
+            }
+            OptionDataType::U32 => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(item.parse().ok()?);
+                }
+                OptionValue::U32(values)
+            }
+            OptionDataType::I32 => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(item.parse().ok()?);
+                }
+                OptionValue::I32(values)
+            }
+            OptionDataType::Bool => {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(match item.to_lowercase().as_str() {
+                        "true" | "yes" | "on" | "1" => true,
+                        "false" | "no" | "off" | "0" => false,
+                        _ => return None,
+                    });
+                }
+                OptionValue::Bool(values)
+            }
+            OptionDataType::Binary => OptionValue::Binary(crate::parse_hex(value)?),
+        };
+
+        // Make sure the encoded value fits the option
+        let length = value.encode().len();
+        if length < descriptor.min_length() {
+            return None;
+        }
+
+        if let Some(max_length) = descriptor.max_length() {
+            if length > max_length {
+                return None;
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        match self {
+            OptionValue::IPAddress(addresses) => {
+                for address in addresses {
+                    bytes.extend_from_slice(address.as_slice());
+                }
+            }
+            OptionValue::IPAddressPair(pairs) => {
+                for (first, second) in pairs {
+                    bytes.extend_from_slice(first.as_slice());
+                    bytes.extend_from_slice(second.as_slice());
+                }
+            }
+            OptionValue::String(string) => bytes.extend_from_slice(string.as_bytes()),
+            OptionValue::DomainName(domains) => {
+                for domain in domains {
+                    for label in domain.split('.') {
+                        bytes.push(label.len() as u8);
+                        bytes.extend_from_slice(label.as_bytes());
+                    }
+                    bytes.push(0);
+                }
+            }
+            OptionValue::U8(values) => bytes.extend_from_slice(values),
+            OptionValue::U16(values) => {
+                for value in values {
+                    bytes.extend_from_slice(&crate::u16_to_slice(*value));
+                }
+            }
+            OptionValue::U32(values) => {
+                for value in values {
+                    bytes.extend_from_slice(&crate::u32_to_slice(*value));
+                }
+            }
+            OptionValue::I32(values) => {
+                for value in values {
+                    bytes.extend_from_slice(&crate::u32_to_slice(*value as u32));
+                }
+            }
+            OptionValue::Bool(values) => {
+                for value in values {
+                    bytes.push(*value as u8);
+                }
+            }
+            OptionValue::Binary(value) => bytes.extend_from_slice(value),
+        }
+
+        bytes
+    }
+
+    pub fn as_ip_address(&self) -> Option<IPAddress> {
+        match self {
+            OptionValue::IPAddress(addresses) => addresses.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(&self) -> Option<u8> {
+        match self {
+            OptionValue::U8(values) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u16(&self) -> Option<u16> {
+        match self {
+            OptionValue::U16(values) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8_list(&self) -> Option<&[u8]> {
+        match self {
+            OptionValue::U8(values) => Some(values.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn as_binary(&self) -> Option<&[u8]> {
+        match self {
+            OptionValue::Binary(value) => Some(value.as_slice()),
+            _ => None,
+        }
+    }
+}
+
+// Decodes one domain name starting at "offset", returning the name and the
+// offset just after it. Compression pointers may refer back into the option.
+fn decode_domain_name(bytes: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut position = offset;
+    let mut next = None;
+    let mut pointers = 0;
+
+    loop {
+        let length = *bytes.get(position)? as usize;
+
+        if length == 0 {
+            position += 1;
+            break;
+        } else if length & 0xC0 == 0xC0 {
+            let target = (length & 0x3F) << 8 | *bytes.get(position + 1)? as usize;
+
+            pointers += 1;
+            if pointers > MAX_DOMAIN_POINTERS || target >= position {
+                return None;
+            }
+
+            if next.is_none() {
+                next = Some(position + 2);
+            }
+            position = target;
+        } else if length > 63 {
+            return None;
+        } else {
+            let label = bytes.get(position + 1..position + 1 + length)?;
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            position += 1 + length;
+        }
+    }
+
+    Some((labels.join("."), next.unwrap_or(position)))
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<String> = match self {
+            OptionValue::IPAddress(addresses) => addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+            OptionValue::IPAddressPair(pairs) => pairs
+                .iter()
+                .map(|(first, second)| format!("{} {}", first, second))
+                .collect(),
+            OptionValue::String(string) => vec![string.clone()],
+            OptionValue::DomainName(domains) => domains.clone(),
+            OptionValue::U8(values) => values.iter().map(|value| value.to_string()).collect(),
+            OptionValue::U16(values) => values.iter().map(|value| value.to_string()).collect(),
+            OptionValue::U32(values) => values.iter().map(|value| value.to_string()).collect(),
+            OptionValue::I32(values) => values.iter().map(|value| value.to_string()).collect(),
+            OptionValue::Bool(values) => values.iter().map(|value| value.to_string()).collect(),
+            OptionValue::Binary(value) => vec![value
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<String>>()
+                .join(":")],
+        };
+
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+impl std::error::Error for OptionValueError {}
+
+impl std::fmt::Display for OptionValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OptionValueError::TooShort(length, min_length) => format!(
+                    "Value too short ({} bytes, at least {} expected)",
+                    length, min_length
+                ),
+                OptionValueError::TooLong(length, max_length) => format!(
+                    "Value too long ({} bytes, at most {} expected)",
+                    length, max_length
+                ),
+                OptionValueError::InvalidLength(length, data_type) =>
+                    format!("Invalid length for {} value ({} bytes)", data_type, length),
+                OptionValueError::InvalidBool(value) =>
+                    format!("Invalid boolean value ({})", value),
+                OptionValueError::InvalidDomainName => "Invalid domain name".to_owned(),
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dhcp::DHCPOptionClass;
+
+    fn decode(
+        option_class: DHCPOptionClass,
+        bytes: &[u8],
+    ) -> Result<OptionValue, OptionValueError> {
+        OptionValue::decode(option_class.descriptor(), bytes)
+    }
+
+    fn parse(option_class: DHCPOptionClass, value: &str) -> Option<OptionValue> {
+        OptionValue::parse(option_class.descriptor(), value)
+    }
+
+    #[test]
+    fn decode_ip_address_list() {
+        assert_eq!(
+            decode(DHCPOptionClass::DomainServer, &[1, 1, 1, 1, 8, 8, 8, 8]).unwrap(),
+            OptionValue::IPAddress(vec![
+                IPAddress::new([1, 1, 1, 1]),
+                IPAddress::new([8, 8, 8, 8])
+            ])
+        );
+
+        match decode(DHCPOptionClass::DomainServer, &[1, 1, 1, 1, 8]) {
+            Err(OptionValueError::InvalidLength(5, OptionDataType::IPAddress)) => {}
+            result => panic!("Expected an invalid length, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn decode_checks_length() {
+        match decode(DHCPOptionClass::SubnetMask, &[255, 255, 255]) {
+            Err(OptionValueError::TooShort(3, 4)) => {}
+            result => panic!("Expected too short, got {:?}", result),
+        }
+
+        match decode(DHCPOptionClass::SubnetMask, &[255, 255, 255, 0, 0]) {
+            Err(OptionValueError::TooLong(5, 4)) => {}
+            result => panic!("Expected too long, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn decode_string_stops_at_null() {
+        assert_eq!(
+            decode(DHCPOptionClass::Hostname, b"host\0junk").unwrap(),
+            OptionValue::String("host".to_owned())
+        );
+    }
+
+    #[test]
+    fn decode_bool() {
+        assert_eq!(
+            decode(DHCPOptionClass::ForwardOnOff, &[1]).unwrap(),
+            OptionValue::Bool(vec![true])
+        );
+
+        match decode(DHCPOptionClass::ForwardOnOff, &[2]) {
+            Err(OptionValueError::InvalidBool(2)) => {}
+            result => panic!("Expected an invalid bool, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn decode_domain_names_with_pointers() {
+        // "example.com" followed by "www" and a pointer back to it
+        let bytes = [
+            7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0, 3, b'w', b'w',
+            b'w', 0xC0, 0,
+        ];
+
+        assert_eq!(
+            decode(DHCPOptionClass::DomainSearch, &bytes).unwrap(),
+            OptionValue::DomainName(vec!["example.com".to_owned(), "www.example.com".to_owned()])
+        );
+    }
+
+    #[test]
+    fn decode_rejects_forward_pointers() {
+        match decode(DHCPOptionClass::DomainSearch, &[0xC0, 2, 0]) {
+            Err(OptionValueError::InvalidDomainName) => {}
+            result => panic!("Expected an invalid domain name, got {:?}", result),
+        }
+
+        match decode(DHCPOptionClass::DomainSearch, &[3, b'w', b'w']) {
+            Err(OptionValueError::InvalidDomainName) => {}
+            result => panic!("Expected an invalid domain name, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_configuration_values() {
+        assert_eq!(
+            parse(DHCPOptionClass::DomainServer, "1.1.1.1, 8.8.8.8"),
+            Some(OptionValue::IPAddress(vec![
+                IPAddress::new([1, 1, 1, 1]),
+                IPAddress::new([8, 8, 8, 8])
+            ]))
+        );
+        assert_eq!(
+            parse(DHCPOptionClass::MaxDGAssembly, "1500"),
+            Some(OptionValue::U16(vec![1500]))
+        );
+        assert_eq!(
+            parse(DHCPOptionClass::ForwardOnOff, "yes"),
+            Some(OptionValue::Bool(vec![true]))
+        );
+        assert_eq!(
+            parse(DHCPOptionClass::VendorSpecific, "0A:0B"),
+            Some(OptionValue::Binary(vec![10, 11]))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_values() {
+        assert_eq!(parse(DHCPOptionClass::DomainServer, "1.1.1"), None);
+        assert_eq!(parse(DHCPOptionClass::MaxDGAssembly, "70000"), None);
+        assert_eq!(parse(DHCPOptionClass::ForwardOnOff, "maybe"), None);
+        assert_eq!(parse(DHCPOptionClass::DomainSearch, "example..com"), None);
+
+        // Values must fit the option's length
+        assert_eq!(parse(DHCPOptionClass::Hostname, ""), None);
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        for (option_class, value) in [
+            (DHCPOptionClass::DomainServer, "1.1.1.1, 8.8.8.8"),
+            (DHCPOptionClass::MaxDGAssembly, "1500"),
+            (DHCPOptionClass::ForwardOnOff, "false"),
+            (
+                DHCPOptionClass::DomainSearch,
+                "example.com, www.example.com",
+            ),
+            (DHCPOptionClass::Hostname, "workstation"),
+        ] {
+            let value = parse(option_class, value).unwrap();
+            assert_eq!(decode(option_class, &value.encode()).unwrap(), value);
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use super::{DHCPOption, DHCPOptionClass, HardwareType, MessageType};
+use super::{
+    DHCPOption, DHCPOptionClass, HardwareAddress, HardwareType, MessageType, BOOT_FILE_OFFSET,
+    MAGIC, MAGIC_OFFSET, OPTIONS_OFFSET, OVERLOAD_BOOT_FILE, OVERLOAD_SERVER_NAME,
+    SERVER_NAME_OFFSET,
+};
 use crate::IPAddress;
 
 pub struct DHCPPacket {
@@ -14,18 +18,30 @@ pub struct DHCPPacket {
     server_ip_address: IPAddress,
     gateway_ip_address: IPAddress,
     client_hardware_address: [u8; 16],
+    server_name: [u8; 64],
+    boot_file: [u8; 128],
+    vendor_extensions: bool,
     options: Vec<DHCPOption>,
+    relay_agent_information: Option<Vec<u8>>,
+    max_message_size: usize,
+    bootp: bool,
 }
 
 #[derive(Debug)]
-pub enum PacketParseError {
-    TooShort(usize),
-    NoEndOption,
-    InvalidMagic([u8; 4]),
-    InvalidMessageType(u8),
-    ParseOptionError(super::ParseOptionError),
+pub enum PacketEncodeError {
+    BufferTooSmall(usize),
 }
 
+// Every client must accept messages of this size (RFC 2131 section 2), the
+// limit covers the I.P. and U.D.P. headers
+const MIN_MAX_MESSAGE_SIZE: usize = 576;
+const IP_UDP_HEADER_SIZE: usize = 28;
+
+// BOOTP messages have a fixed 64 byte vendor area (RFC 951)
+pub const BOOTP_MESSAGE_SIZE: usize = 300;
+
+const MAX_OPTION_LENGTH: usize = 255;
+
 impl DHCPPacket {
     pub fn new(
         transaction_id: u32,
@@ -34,12 +50,12 @@ impl DHCPPacket {
         your_ip_address: IPAddress,
         server_ip_address: IPAddress,
         gateway_ip_address: IPAddress,
-        client_hardware_address: [u8; 16],
+        client_hardware_address: &HardwareAddress,
     ) -> Self {
         DHCPPacket {
             message_type: MessageType::Reply,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: 6,
+            hardware_type: client_hardware_address.hardware_type(),
+            hardware_address_length: client_hardware_address.length(),
             hops: 0,
             transaction_id,
             seconds: 0,
@@ -48,79 +64,14 @@ impl DHCPPacket {
             your_ip_address,
             server_ip_address,
             gateway_ip_address,
-            client_hardware_address,
+            client_hardware_address: *client_hardware_address.padded(),
+            server_name: [0; 64],
+            boot_file: [0; 128],
+            vendor_extensions: true,
             options: Vec::new(),
-        }
-    }
-
-    pub fn parse(packet: &[u8]) -> Result<Self, PacketParseError> {
-        if packet.len() < 241 {
-            return Err(PacketParseError::TooShort(packet.len()));
-        }
-
-        // Parse basic information
-        let message_type = match MessageType::parse(packet[0]) {
-            Some(message_type) => message_type,
-            None => return Err(PacketParseError::InvalidMessageType(packet[0])),
-        };
-        let hardware_type = HardwareType::parse(packet[1]);
-        let hardware_address_length = packet[2];
-        let hops = packet[3];
-        let transaction_id = crate::slice_to_u32(&packet[4..]);
-        let seconds = crate::slice_to_u16(&packet[8..]);
-        let flags = crate::slice_to_u16(&packet[10..]);
-        let client_ip_address = IPAddress::new([packet[12], packet[13], packet[14], packet[15]]);
-        let your_ip_address = IPAddress::new([packet[16], packet[17], packet[18], packet[19]]);
-        let server_ip_address = IPAddress::new([packet[20], packet[21], packet[22], packet[23]]);
-        let gateway_ip_address = IPAddress::new([packet[24], packet[25], packet[26], packet[27]]);
-        let client_hardware_address = [
-            packet[28], packet[29], packet[30], packet[31], packet[32], packet[33], packet[34],
-            packet[35], packet[36], packet[37], packet[38], packet[39], packet[40], packet[41],
-            packet[42], packet[43],
-        ];
-
-        // Parse options
-        let mut options = Vec::new();
-        let mut i = 240;
-        loop {
-            if i >= packet.len() {
-                return Err(PacketParseError::NoEndOption);
-            }
-
-            let option = DHCPOption::parse(&packet[i..])?;
-
-            if option.class() == DHCPOptionClass::End {
-                break;
-            }
-
-            i += option.value().len() + 2;
-
-            options.push(option);
-        }
-
-        if packet[236] != 99 || packet[237] != 130 || packet[238] != 83 || packet[239] != 99 {
-            Err(PacketParseError::InvalidMagic([
-                packet[236],
-                packet[237],
-                packet[238],
-                packet[239],
-            ]))
-        } else {
-            Ok(DHCPPacket {
-                message_type,
-                hardware_type,
-                hardware_address_length,
-                hops,
-                transaction_id,
-                seconds,
-                flags,
-                client_ip_address,
-                your_ip_address,
-                server_ip_address,
-                gateway_ip_address,
-                client_hardware_address,
-                options,
-            })
+            relay_agent_information: None,
+            max_message_size: MIN_MAX_MESSAGE_SIZE,
+            bootp: false,
         }
     }
 
@@ -129,137 +80,503 @@ impl DHCPPacket {
             .push(DHCPOption::new(option_class, Vec::from(value)))
     }
 
-    pub fn message_type(&self) -> MessageType {
-        self.message_type
-    }
-
-    pub fn hardware_type(&self) -> HardwareType {
-        self.hardware_type
-    }
-
-    pub fn hardware_address_length(&self) -> u8 {
-        self.hardware_address_length
+    // The relay agent information is always the last option so relay agents
+    // can find and remove it (RFC 3046 section 2.2). Room is kept for it so
+    // it is never dropped to fit other options.
+    pub fn set_relay_agent_information(&mut self, value: &[u8]) {
+        self.relay_agent_information = Some(value.to_vec());
     }
 
-    pub fn transaction_id(&self) -> u32 {
-        self.transaction_id
+    // Options which don't fit are overloaded or dropped when the packet is
+    // generated, clients can't ask for less than the minimum
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size.max(MIN_MAX_MESSAGE_SIZE);
     }
 
-    pub fn client_ip_address(&self) -> IPAddress {
-        self.client_ip_address
+    // The packet is generated as a BOOTP reply, options are only included if
+    // the client used the vendor area for them
+    pub fn set_bootp(&mut self, vendor_extensions: bool) {
+        self.bootp = true;
+        self.vendor_extensions = vendor_extensions;
     }
 
-    pub fn gateway_ip_address(&self) -> IPAddress {
-        self.gateway_ip_address
+    // Null terminated, so at most 127 bytes are kept
+    pub fn set_boot_file(&mut self, boot_file: &[u8]) {
+        let length = boot_file.len().min(self.boot_file.len() - 1);
+        self.boot_file.fill(0);
+        self.boot_file[..length].copy_from_slice(&boot_file[..length]);
     }
 
-    pub fn flags(&self) -> u16 {
-        self.flags
-    }
+    pub fn generate(&self) -> Vec<u8> {
+        let mut buffer = vec![0; self.max_message_size - IP_UDP_HEADER_SIZE];
+        // The buffer always has room for at least the minimum message size
+        let length = self.encode(&mut buffer).unwrap_or_default();
 
-    pub fn client_hardware_address(&self) -> &[u8; 16] {
-        &self.client_hardware_address
+        buffer.truncate(length);
+        buffer
     }
 
-    pub fn get_option(&self, option_class: DHCPOptionClass) -> Option<&[u8]> {
-        for option in &self.options {
-            if option.class() == option_class {
-                return Some(option.value());
+    // Writes the packet into "buffer" and returns its length. The options are
+    // limited by the smaller of the buffer and the maximum message size.
+    pub fn encode(&self, buffer: &mut [u8]) -> Result<usize, PacketEncodeError> {
+        let size = if self.bootp {
+            if buffer.len() < BOOTP_MESSAGE_SIZE {
+                return Err(PacketEncodeError::BufferTooSmall(buffer.len()));
             }
-        }
 
-        None
-    }
+            BOOTP_MESSAGE_SIZE
+        } else {
+            buffer.len().min(self.max_message_size - IP_UDP_HEADER_SIZE)
+        };
+        if size < OPTIONS_OFFSET + 1 {
+            return Err(PacketEncodeError::BufferTooSmall(buffer.len()));
+        }
 
-    pub fn generate(&self) -> Vec<u8> {
         // First four bytes
-        let mut vec = vec![
-            self.message_type.generate(),
-            self.hardware_type.generate(),
-            self.hardware_address_length,
-            self.hops,
-        ];
+        buffer[0] = self.message_type.generate();
+        buffer[1] = self.hardware_type.generate();
+        buffer[2] = self.hardware_address_length;
+        buffer[3] = self.hops;
 
         // Multibyte parameters
-        vec.extend_from_slice(&crate::u32_to_slice(self.transaction_id));
-        vec.extend_from_slice(&crate::u16_to_slice(self.seconds));
-        vec.extend_from_slice(&crate::u16_to_slice(self.flags));
-        vec.extend_from_slice(self.client_ip_address.as_slice());
-        vec.extend_from_slice(self.your_ip_address.as_slice());
-        vec.extend_from_slice(self.server_ip_address.as_slice());
-        vec.extend_from_slice(self.gateway_ip_address.as_slice());
-        vec.extend_from_slice(&self.client_hardware_address);
+        buffer[4..8].copy_from_slice(&crate::u32_to_slice(self.transaction_id));
+        buffer[8..10].copy_from_slice(&crate::u16_to_slice(self.seconds));
+        buffer[10..12].copy_from_slice(&crate::u16_to_slice(self.flags));
+        buffer[12..16].copy_from_slice(self.client_ip_address.as_slice());
+        buffer[16..20].copy_from_slice(self.your_ip_address.as_slice());
+        buffer[20..24].copy_from_slice(self.server_ip_address.as_slice());
+        buffer[24..28].copy_from_slice(self.gateway_ip_address.as_slice());
+        buffer[28..SERVER_NAME_OFFSET].copy_from_slice(&self.client_hardware_address);
 
         // Server name and bootfile
-        for _ in 0..64 + 128 {
-            vec.push(0);
+        buffer[SERVER_NAME_OFFSET..BOOT_FILE_OFFSET].copy_from_slice(&self.server_name);
+        buffer[BOOT_FILE_OFFSET..MAGIC_OFFSET].copy_from_slice(&self.boot_file);
+
+        if !self.vendor_extensions {
+            buffer[MAGIC_OFFSET..size].fill(0);
+            return Ok(size);
         }
 
         // Magic
-        vec.extend_from_slice(&[99, 130, 83, 99]);
+        buffer[MAGIC_OFFSET..OPTIONS_OFFSET].copy_from_slice(&MAGIC);
+
+        // Options which don't fit in the options field are moved into the
+        // server name and boot file fields if they are unused. Each area is
+        // the overload flag it sets, its start, the current position and its
+        // end.
+        let mut areas = [
+            (0, OPTIONS_OFFSET, OPTIONS_OFFSET, size),
+            (
+                OVERLOAD_BOOT_FILE,
+                BOOT_FILE_OFFSET,
+                BOOT_FILE_OFFSET,
+                BOOT_FILE_OFFSET,
+            ),
+            (
+                OVERLOAD_SERVER_NAME,
+                SERVER_NAME_OFFSET,
+                SERVER_NAME_OFFSET,
+                SERVER_NAME_OFFSET,
+            ),
+        ];
 
-        // Options
+        let relay_agent_information_length = match &self.relay_agent_information {
+            Some(value) => encoded_length(value),
+            None => 0,
+        };
+        let options_length: usize = self
+            .options
+            .iter()
+            .filter(|option| is_encoded(option))
+            .map(|option| encoded_length(option.value()))
+            .sum();
+        // BOOTP clients don't understand overloading
+        let overloading = !self.bootp
+            && options_length + relay_agent_information_length + 1 > size - OPTIONS_OFFSET;
+        if overloading {
+            // Leave room for the overload option
+            areas[0].1 += 3;
+            areas[0].2 += 3;
+
+            if self.boot_file.iter().all(|byte| *byte == 0) {
+                areas[1].3 = MAGIC_OFFSET;
+            }
+            if self.server_name.iter().all(|byte| *byte == 0) {
+                areas[2].3 = BOOT_FILE_OFFSET;
+            }
+        }
+
+        // The relay agent information goes at the end of the options field,
+        // it is left out only if it can't fit even on its own
+        let relay_agent_information = match &self.relay_agent_information {
+            Some(value) => {
+                if areas[0].2 + relay_agent_information_length < areas[0].3 {
+                    areas[0].3 -= relay_agent_information_length;
+                    Some(value)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        // Every area needs room for an end option. Values longer than 255
+        // bytes are split into consecutive options (RFC 3396) which must
+        // stay in order across the areas, options which can't be placed
+        // entirely are dropped.
         for option in &self.options {
-            vec.extend_from_slice(option.generate().as_slice())
+            if !is_encoded(option) {
+                continue;
+            }
+
+            let positions = [areas[0].2, areas[1].2, areas[2].2];
+            let value = option.value();
+
+            let mut first_area = 0;
+            let mut placed;
+            let mut offset = 0;
+            loop {
+                let segment = &value[offset..value.len().min(offset + MAX_OPTION_LENGTH)];
+
+                placed = false;
+                for (i, area) in areas.iter_mut().enumerate().skip(first_area) {
+                    let position = area.2;
+                    if position + segment.len() + 3 <= area.3 {
+                        buffer[position] = option.class().generate();
+                        buffer[position + 1] = segment.len() as u8;
+                        buffer[position + 2..position + 2 + segment.len()].copy_from_slice(segment);
+                        area.2 += segment.len() + 2;
+                        first_area = i;
+                        placed = true;
+                        break;
+                    }
+                }
+
+                offset += segment.len();
+                if !placed || offset >= value.len() {
+                    break;
+                }
+            }
+
+            if !placed {
+                for (area, position) in areas.iter_mut().zip(positions) {
+                    area.2 = position;
+                }
+            }
+        }
+
+        if let Some(value) = relay_agent_information {
+            let class = DHCPOptionClass::RelayAgentInformation.generate();
+            for segment in value.chunks(MAX_OPTION_LENGTH) {
+                let position = areas[0].2;
+                buffer[position] = class;
+                buffer[position + 1] = segment.len() as u8;
+                buffer[position + 2..position + 2 + segment.len()].copy_from_slice(segment);
+                areas[0].2 += segment.len() + 2;
+            }
+            if value.is_empty() {
+                buffer[areas[0].2] = class;
+                buffer[areas[0].2 + 1] = 0;
+                areas[0].2 += 2;
+            }
+            areas[0].3 += relay_agent_information_length;
+        }
+
+        // Terminate the areas
+        let mut overload = 0;
+        for (flag, start, position, end) in areas {
+            if flag == 0 {
+                buffer[position] = DHCPOptionClass::End.generate();
+            } else if position > start {
+                buffer[position] = DHCPOptionClass::End.generate();
+                buffer[position + 1..end].fill(0);
+                overload |= flag;
+            }
+        }
+
+        if overloading {
+            buffer[OPTIONS_OFFSET..OPTIONS_OFFSET + 3].copy_from_slice(&if overload != 0 {
+                [DHCPOptionClass::Overload.generate(), 1, overload]
+            } else {
+                [DHCPOptionClass::Pad.generate(); 3]
+            });
+        }
+
+        // BOOTP replies are padded to the full size
+        let length = areas[0].2 + 1;
+        if self.bootp {
+            buffer[length..size].fill(0);
+            return Ok(size);
         }
 
-        vec
+        Ok(length)
     }
 }
 
+// Pad, end and overload are generated by the encoder
+fn is_encoded(option: &DHCPOption) -> bool {
+    !matches!(
+        option.class(),
+        DHCPOptionClass::Pad | DHCPOptionClass::End | DHCPOptionClass::Overload
+    )
+}
+
+fn encoded_length(value: &[u8]) -> usize {
+    let segments = value.len().div_ceil(MAX_OPTION_LENGTH);
+    value.len() + 2 * segments.max(1)
+}
+
+// The server name and boot file fields are null terminated strings
+pub fn field_to_string(field: &[u8]) -> String {
+    let end = match field.iter().position(|byte| *byte == 0) {
+        Some(end) => end,
+        None => field.len(),
+    };
+
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
 impl std::fmt::Display for DHCPPacket {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Message Type: {}\n", self.message_type)?;
-        write!(f, "Hardware Type: {}\n", self.hardware_type)?;
-        write!(
+        writeln!(f, "Message Type: {}", self.message_type)?;
+        writeln!(f, "Hardware Type: {}", self.hardware_type)?;
+        writeln!(
             f,
-            "Hardware Address Length: {}\n",
+            "Hardware Address Length: {}",
             self.hardware_address_length
         )?;
-        write!(f, "Hops: {}\n", self.hops)?;
-        write!(f, "Transaction ID: {}\n", self.transaction_id)?;
-        write!(f, "Seconds: {}\n", self.seconds)?;
-        write!(f, "Flags: {}\n", self.flags)?;
-        write!(f, "Client I.P. Address: {}\n", self.client_ip_address)?;
-        write!(f, "Your I.P. Address: {}\n", self.your_ip_address)?;
-        write!(f, "Server I.P. Address: {}\n", self.server_ip_address)?;
-        write!(f, "Gateway I.P. Address: {}\n", self.gateway_ip_address)?;
-        write!(
+        writeln!(f, "Hops: {}", self.hops)?;
+        writeln!(f, "Transaction ID: {}", self.transaction_id)?;
+        writeln!(f, "Seconds: {}", self.seconds)?;
+        writeln!(f, "Flags: {}", self.flags)?;
+        writeln!(f, "Client I.P. Address: {}", self.client_ip_address)?;
+        writeln!(f, "Your I.P. Address: {}", self.your_ip_address)?;
+        writeln!(f, "Server I.P. Address: {}", self.server_ip_address)?;
+        writeln!(f, "Gateway I.P. Address: {}", self.gateway_ip_address)?;
+        writeln!(
             f,
-            "Client Hardware Address: {:?}\n",
+            "Client Hardware Address: {:?}",
             self.client_hardware_address
         )?;
-        write!(f, "Options:\n")?;
+        writeln!(f, "Server Name: {}", field_to_string(&self.server_name))?;
+        writeln!(f, "Boot File: {}", field_to_string(&self.boot_file))?;
+        writeln!(f, "Options:")?;
         for option in &self.options {
-            write!(f, "    {}\n", option)?;
+            writeln!(f, "    {}", option)?;
+        }
+        if let Some(value) = &self.relay_agent_information {
+            writeln!(
+                f,
+                "    {}",
+                DHCPOption::new(DHCPOptionClass::RelayAgentInformation, value.clone())
+            )?;
         }
         Ok(())
     }
 }
 
-impl std::error::Error for PacketParseError {}
+impl std::error::Error for PacketEncodeError {}
 
-impl std::fmt::Display for PacketParseError {
+impl std::fmt::Display for PacketEncodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                PacketParseError::TooShort(length) => format!("Too short ({})", length),
-                PacketParseError::NoEndOption => format!("No end option"),
-                PacketParseError::InvalidMagic(magic) =>
-                    format!("Invalid magic value ({:?})", magic),
-                PacketParseError::InvalidMessageType(message_type) =>
-                    format!("Invalid message type ({})", message_type),
-                PacketParseError::ParseOptionError(error) =>
-                    format!("Unable to parse option ({})", error),
+                PacketEncodeError::BufferTooSmall(length) =>
+                    format!("Buffer too small ({})", length),
             }
         )
     }
 }
 
-impl From<super::ParseOptionError> for PacketParseError {
-    fn from(error: super::ParseOptionError) -> Self {
-        PacketParseError::ParseOptionError(error)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dhcp::{DHCPPacketView, ParseMode};
+
+    fn reply() -> DHCPPacket {
+        DHCPPacket::new(
+            0x12345678,
+            0,
+            IPAddress::new([0, 0, 0, 0]),
+            IPAddress::new([192, 168, 1, 100]),
+            IPAddress::new([192, 168, 1, 1]),
+            IPAddress::new([0, 0, 0, 0]),
+            &HardwareAddress::new(HardwareType::Ethernet, &[0, 0x11, 0x22, 0x33, 0x44, 0x55]),
+        )
+    }
+
+    #[test]
+    fn small_options_are_not_overloaded() {
+        let mut packet = reply();
+        packet.add_option(DHCPOptionClass::DHCPMsgType, &[2]);
+        packet.add_option(DHCPOptionClass::Hostname, b"workstation");
+        packet.set_boot_file(b"pxelinux.0");
+
+        let bytes = packet.generate();
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert_eq!(
+            view.boot_file().map(field_to_string),
+            Some("pxelinux.0".to_owned())
+        );
+        assert!(view.server_name().is_some());
+        assert_eq!(
+            view.get_option(DHCPOptionClass::Hostname).as_deref(),
+            Some(&b"workstation"[..])
+        );
+        assert_eq!(bytes.len(), OPTIONS_OFFSET + 3 + 13 + 1);
+    }
+
+    #[test]
+    fn options_overflow_into_file_then_sname() {
+        // Each option fills most of an area, the minimum message size leaves
+        // 308 bytes for the options field
+        let mut packet = reply();
+        packet.add_option(DHCPOptionClass::VendorSpecific, &[1; 250]);
+        packet.add_option(DHCPOptionClass::Hostname, &[b'a'; 100]);
+        packet.add_option(DHCPOptionClass::RootPath, &[b'b'; 60]);
+
+        let bytes = packet.generate();
+        assert_eq!(bytes[OPTIONS_OFFSET..OPTIONS_OFFSET + 3], [52, 1, 3]);
+        assert_eq!(
+            bytes[BOOT_FILE_OFFSET],
+            DHCPOptionClass::Hostname.generate()
+        );
+        assert_eq!(
+            bytes[SERVER_NAME_OFFSET],
+            DHCPOptionClass::RootPath.generate()
+        );
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert!(view.boot_file().is_none());
+        assert!(view.server_name().is_none());
+        assert_eq!(
+            view.get_option(DHCPOptionClass::VendorSpecific).as_deref(),
+            Some(&[1; 250][..])
+        );
+        assert_eq!(
+            view.get_option(DHCPOptionClass::Hostname).as_deref(),
+            Some(&[b'a'; 100][..])
+        );
+        assert_eq!(
+            view.get_option(DHCPOptionClass::RootPath).as_deref(),
+            Some(&[b'b'; 60][..])
+        );
+    }
+
+    #[test]
+    fn used_fields_are_not_overloaded() {
+        let mut packet = reply();
+        packet.set_boot_file(b"pxelinux.0");
+        packet.add_option(DHCPOptionClass::VendorSpecific, &[1; 250]);
+        packet.add_option(DHCPOptionClass::Hostname, &[b'a'; 55]);
+
+        let bytes = packet.generate();
+        assert_eq!(bytes[OPTIONS_OFFSET..OPTIONS_OFFSET + 3], [52, 1, 2]);
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert_eq!(
+            view.boot_file().map(field_to_string),
+            Some("pxelinux.0".to_owned())
+        );
+        assert!(view.server_name().is_none());
+        assert!(view.get_option(DHCPOptionClass::Hostname).is_some());
+    }
+
+    #[test]
+    fn options_that_do_not_fit_are_dropped() {
+        let mut packet = reply();
+        packet.add_option(DHCPOptionClass::DHCPMsgType, &[2]);
+        packet.add_option(DHCPOptionClass::VendorSpecific, &[1; 1000]);
+        packet.add_option(DHCPOptionClass::Hostname, b"workstation");
+
+        let bytes = packet.generate();
+        assert!(bytes.len() <= MIN_MAX_MESSAGE_SIZE - IP_UDP_HEADER_SIZE);
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert!(view.get_option(DHCPOptionClass::VendorSpecific).is_none());
+        assert!(view.get_option(DHCPOptionClass::DHCPMsgType).is_some());
+        assert!(view.get_option(DHCPOptionClass::Hostname).is_some());
+    }
+
+    #[test]
+    fn long_options_are_split() {
+        let value: Vec<u8> = (0..600).map(|i| i as u8).collect();
+
+        let mut packet = reply();
+        packet.set_max_message_size(1500);
+        packet.add_option(DHCPOptionClass::VendorSpecific, &value);
+
+        let bytes = packet.generate();
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        let lengths: Vec<usize> = view.options().map(|(_, segment)| segment.len()).collect();
+        assert_eq!(lengths, [255, 255, 90]);
+        assert_eq!(
+            view.get_option(DHCPOptionClass::VendorSpecific).as_deref(),
+            Some(value.as_slice())
+        );
+    }
+
+    #[test]
+    fn split_options_stay_in_order_across_areas() {
+        // The first part fills the options field and the rest moves into the
+        // file field
+        let value: Vec<u8> = (0..350).map(|i| i as u8).collect();
+
+        let mut packet = reply();
+        packet.add_option(DHCPOptionClass::VendorSpecific, &value);
+
+        let bytes = packet.generate();
+        assert_eq!(bytes[OPTIONS_OFFSET..OPTIONS_OFFSET + 3], [52, 1, 1]);
+        assert_eq!(bytes[BOOT_FILE_OFFSET + 1], 95);
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert_eq!(
+            view.get_option(DHCPOptionClass::VendorSpecific).as_deref(),
+            Some(value.as_slice())
+        );
+    }
+
+    #[test]
+    fn empty_options_are_encoded() {
+        let mut packet = reply();
+        packet.add_option(DHCPOptionClass::RapidCommit, &[]);
+
+        let bytes = packet.generate();
+        assert_eq!(bytes[OPTIONS_OFFSET..OPTIONS_OFFSET + 3], [80, 0, 255]);
+    }
+
+    #[test]
+    fn relay_agent_information_is_the_last_option() {
+        let mut packet = reply();
+        packet.set_relay_agent_information(&[1, 3, b'e', b't', b'h']);
+        packet.add_option(DHCPOptionClass::DHCPMsgType, &[2]);
+        packet.add_option(DHCPOptionClass::VendorSpecific, &[1; 300]);
+
+        // The other options are overloaded but option 82 stays at the end of
+        // the options field
+        let bytes = packet.generate();
+        assert_eq!(bytes[OPTIONS_OFFSET], DHCPOptionClass::Overload.generate());
+        assert_eq!(
+            bytes[bytes.len() - 8..],
+            [82, 5, 1, 3, b'e', b't', b'h', 255]
+        );
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert_eq!(
+            view.get_option(DHCPOptionClass::VendorSpecific).as_deref(),
+            Some(&[1; 300][..])
+        );
+    }
+
+    #[test]
+    fn encode_checks_buffer_size() {
+        let packet = reply();
+        let mut buffer = [0; OPTIONS_OFFSET];
+        match packet.encode(&mut buffer) {
+            Err(PacketEncodeError::BufferTooSmall(length)) => assert_eq!(length, OPTIONS_OFFSET),
+            result => panic!("Expected the buffer to be too small, got {:?}", result),
+        }
     }
 }
@@ -0,0 +1,171 @@
+use super::ParseOptionError;
+use crate::IPAddress;
+
+// The relay agent information option (RFC 3046) is made of sub-options which
+// use the same code, length, value layout as options but without pad or end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayAgentInformation {
+    sub_options: Vec<(u8, Vec<u8>)>,
+}
+
+const SUB_OPTION_CIRCUIT_ID: u8 = 1;
+const SUB_OPTION_REMOTE_ID: u8 = 2;
+// RFC 3527
+const SUB_OPTION_LINK_SELECTION: u8 = 5;
+// RFC 3993
+const SUB_OPTION_SUBSCRIBER_ID: u8 = 6;
+
+impl RelayAgentInformation {
+    pub fn parse(value: &[u8]) -> Result<Self, ParseOptionError> {
+        let mut sub_options = Vec::new();
+
+        let mut i = 0;
+        while i < value.len() {
+            if i + 1 >= value.len() {
+                return Err(ParseOptionError::MissingLength);
+            }
+
+            let length = value[i + 1] as usize;
+            if i + 2 + length > value.len() {
+                return Err(ParseOptionError::InvalidLength);
+            }
+
+            sub_options.push((value[i], value[i + 2..i + 2 + length].to_vec()));
+            i += 2 + length;
+        }
+
+        Ok(RelayAgentInformation { sub_options })
+    }
+
+    // Relay agents expect the option to be echoed back unchanged
+    pub fn generate(&self) -> Vec<u8> {
+        let mut value = Vec::new();
+        for (code, sub_option) in &self.sub_options {
+            value.push(*code);
+            value.push(sub_option.len() as u8);
+            value.extend_from_slice(sub_option);
+        }
+        value
+    }
+
+    pub fn circuit_id(&self) -> Option<&[u8]> {
+        self.get(SUB_OPTION_CIRCUIT_ID)
+    }
+
+    pub fn remote_id(&self) -> Option<&[u8]> {
+        self.get(SUB_OPTION_REMOTE_ID)
+    }
+
+    pub fn link_selection(&self) -> Option<IPAddress> {
+        match self.get(SUB_OPTION_LINK_SELECTION) {
+            Some(address) => match address.len() {
+                4 => Some(IPAddress::new([
+                    address[0], address[1], address[2], address[3],
+                ])),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    pub fn subscriber_id(&self) -> Option<&[u8]> {
+        self.get(SUB_OPTION_SUBSCRIBER_ID)
+    }
+
+    fn get(&self, code: u8) -> Option<&[u8]> {
+        for (sub_option_code, sub_option) in &self.sub_options {
+            if *sub_option_code == code {
+                return Some(sub_option.as_slice());
+            }
+        }
+
+        None
+    }
+}
+
+fn bytes_to_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+impl std::fmt::Display for RelayAgentInformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+
+        if let Some(circuit_id) = self.circuit_id() {
+            parts.push(format!("Circuit ID {}", bytes_to_string(circuit_id)));
+        }
+
+        if let Some(remote_id) = self.remote_id() {
+            parts.push(format!("Remote ID {}", bytes_to_string(remote_id)));
+        }
+
+        if let Some(link_selection) = self.link_selection() {
+            parts.push(format!("Link Selection {}", link_selection));
+        }
+
+        if let Some(subscriber_id) = self.subscriber_id() {
+            parts.push(format!(
+                "Subscriber ID {}",
+                String::from_utf8_lossy(subscriber_id)
+            ));
+        }
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sub_options() {
+        let value = [1, 3, b'e', b't', b'h', 2, 2, 0xAB, 0xCD, 5, 4, 10, 0, 1, 0];
+        let relay_agent_information = RelayAgentInformation::parse(&value).unwrap();
+
+        assert_eq!(relay_agent_information.circuit_id(), Some(&b"eth"[..]));
+        assert_eq!(relay_agent_information.remote_id(), Some(&[0xAB, 0xCD][..]));
+        assert_eq!(
+            relay_agent_information.link_selection(),
+            Some(IPAddress::new([10, 0, 1, 0]))
+        );
+        assert_eq!(relay_agent_information.subscriber_id(), None);
+        assert_eq!(
+            relay_agent_information.to_string(),
+            "Circuit ID 65:74:68, Remote ID AB:CD, Link Selection 10.0.1.0"
+        );
+    }
+
+    #[test]
+    fn generate_echoes_unknown_sub_options() {
+        let value = [9, 2, 1, 2, 1, 1, b'a', 0, 0];
+        let relay_agent_information = RelayAgentInformation::parse(&value).unwrap();
+        assert_eq!(relay_agent_information.generate(), value);
+    }
+
+    #[test]
+    fn link_selection_must_be_an_address() {
+        let relay_agent_information = RelayAgentInformation::parse(&[5, 3, 10, 0, 1]).unwrap();
+        assert_eq!(relay_agent_information.link_selection(), None);
+    }
+
+    #[test]
+    fn truncated_sub_options_are_rejected() {
+        assert!(matches!(
+            RelayAgentInformation::parse(&[1, 3, b'e', b't']),
+            Err(ParseOptionError::InvalidLength)
+        ));
+        assert!(matches!(
+            RelayAgentInformation::parse(&[1, 1, b'e', 2]),
+            Err(ParseOptionError::MissingLength)
+        ));
+        assert_eq!(
+            RelayAgentInformation::parse(&[]).unwrap().generate(),
+            Vec::<u8>::new()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use super::{
+    DHCPOption, DHCPOptionClass, DHCPPacket, DHCPPacketView, HardwareAddress, OptionValue,
+};
+use crate::IPAddress;
+
+// Builds a reply to a request. The transaction ID, flags, relay address,
+// client hardware address and client identifier are taken from the request,
+// the message type and server identifier are always the first options, the
+// relay agent information is always the last and the end option is appended
+// when the packet is generated. BOOTP replies have neither a message type nor
+// a server identifier.
+pub struct ReplyBuilder {
+    transaction_id: u32,
+    flags: u16,
+    client_ip_address: IPAddress,
+    your_ip_address: IPAddress,
+    server_ip_address: IPAddress,
+    gateway_ip_address: IPAddress,
+    client_hardware_address: HardwareAddress,
+    boot_file: Option<Vec<u8>>,
+    message_type: Option<(u8, IPAddress)>,
+    vendor_extensions: bool,
+    options: Vec<DHCPOption>,
+    relay_agent_information: Option<Vec<u8>>,
+}
+
+impl ReplyBuilder {
+    pub fn new(request: &DHCPPacketView, message_type: u8, server_id: IPAddress) -> Self {
+        ReplyBuilder::create(request, Some((message_type, server_id)))
+    }
+
+    pub fn bootp(request: &DHCPPacketView) -> Self {
+        ReplyBuilder::create(request, None)
+    }
+
+    fn create(request: &DHCPPacketView, message_type: Option<(u8, IPAddress)>) -> Self {
+        // The client identifier (RFC 6842) is echoed back unchanged if the
+        // request had it, it comes before other options so it is never
+        // dropped to fit the reply
+        let mut options = Vec::new();
+        if let Some(value) = request.get_option(DHCPOptionClass::ClientID) {
+            options.push(DHCPOption::new(DHCPOptionClass::ClientID, value.to_vec()));
+        }
+
+        ReplyBuilder {
+            transaction_id: request.transaction_id(),
+            flags: request.flags(),
+            client_ip_address: IPAddress::new([0, 0, 0, 0]),
+            your_ip_address: IPAddress::new([0, 0, 0, 0]),
+            server_ip_address: IPAddress::new([0, 0, 0, 0]),
+            gateway_ip_address: request.gateway_ip_address(),
+            client_hardware_address: request.client_hardware_address(),
+            boot_file: None,
+            message_type,
+            vendor_extensions: request.has_vendor_extensions(),
+            options,
+            relay_agent_information: None,
+        }
+    }
+
+    // Echoes the relay agent information unchanged as the last option (RFC
+    // 3046 section 2.2). It must not be echoed to clients which sent it
+    // directly or through a relay it wasn't accepted from (section 2.1).
+    pub fn relay_agent_information(mut self, request: &DHCPPacketView) -> Self {
+        self.relay_agent_information = request
+            .get_option(DHCPOptionClass::RelayAgentInformation)
+            .map(|value| value.to_vec());
+        self
+    }
+
+    // Echoes the subnet selection option unchanged, only if it was used to
+    // choose the client's subnet (RFC 3011 section 3). It is kept at the
+    // front with the client identifier.
+    pub fn subnet_selection(mut self, request: &DHCPPacketView) -> Self {
+        if let Some(value) = request.get_option(DHCPOptionClass::SubnetSelection) {
+            self.options.insert(
+                0,
+                DHCPOption::new(DHCPOptionClass::SubnetSelection, value.to_vec()),
+            );
+        }
+        self
+    }
+
+    pub fn client_ip_address(mut self, client_ip_address: IPAddress) -> Self {
+        self.client_ip_address = client_ip_address;
+        self
+    }
+
+    pub fn your_ip_address(mut self, your_ip_address: IPAddress) -> Self {
+        self.your_ip_address = your_ip_address;
+        self
+    }
+
+    pub fn server_ip_address(mut self, server_ip_address: IPAddress) -> Self {
+        self.server_ip_address = server_ip_address;
+        self
+    }
+
+    pub fn boot_file(mut self, boot_file: &[u8]) -> Self {
+        self.boot_file = Some(boot_file.to_vec());
+        self
+    }
+
+    pub fn lease_times(
+        mut self,
+        address_time: u32,
+        renewal_time: u32,
+        rebinding_time: u32,
+    ) -> Self {
+        self.set_option(
+            DHCPOptionClass::AddressTime,
+            &OptionValue::U32(vec![address_time]),
+        );
+        self.set_option(
+            DHCPOptionClass::RenewalTime,
+            &OptionValue::U32(vec![renewal_time]),
+        );
+        self.set_option(
+            DHCPOptionClass::RebindingTime,
+            &OptionValue::U32(vec![rebinding_time]),
+        );
+        self
+    }
+
+    pub fn option(mut self, option_class: DHCPOptionClass, value: &OptionValue) -> Self {
+        self.set_option(option_class, value);
+        self
+    }
+
+    pub fn build(self) -> DHCPPacket {
+        let mut packet = DHCPPacket::new(
+            self.transaction_id,
+            self.flags,
+            self.client_ip_address,
+            self.your_ip_address,
+            self.server_ip_address,
+            self.gateway_ip_address,
+            &self.client_hardware_address,
+        );
+
+        match self.message_type {
+            Some((message_type, server_id)) => {
+                packet.add_option(DHCPOptionClass::DHCPMsgType, &[message_type]);
+                packet.add_option(DHCPOptionClass::DHCPServerID, server_id.as_slice());
+            }
+            None => packet.set_bootp(self.vendor_extensions),
+        }
+
+        if let Some(boot_file) = &self.boot_file {
+            packet.set_boot_file(boot_file);
+        }
+
+        for option in &self.options {
+            packet.add_option(option.class(), option.value());
+        }
+
+        if let Some(value) = &self.relay_agent_information {
+            packet.set_relay_agent_information(value);
+        }
+
+        packet
+    }
+
+    // Setting an option again replaces it, the message type, server
+    // identifier and echoed options can't be replaced
+    fn set_option(&mut self, option_class: DHCPOptionClass, value: &OptionValue) {
+        match option_class {
+            DHCPOptionClass::DHCPMsgType
+            | DHCPOptionClass::DHCPServerID
+            | DHCPOptionClass::ClientID
+            | DHCPOptionClass::RelayAgentInformation
+            | DHCPOptionClass::SubnetSelection => return,
+            _ => {}
+        }
+
+        self.options.retain(|option| option.class() != option_class);
+        self.options
+            .push(DHCPOption::new(option_class, value.encode()));
+    }
+}
@@ -0,0 +1,516 @@
+use super::{
+    DHCPOption, DHCPOptionClass, HardwareAddress, HardwareType, MessageType, OptionValue,
+    OptionValueError, ParseOptionError,
+};
+use crate::IPAddress;
+use std::borrow::Cow;
+
+// A packet which is parsed in place, options are located but never copied
+pub struct DHCPPacketView<'a> {
+    packet: &'a [u8],
+    message_type: MessageType,
+    vendor_extensions: bool,
+    overload: u8,
+    // Code, value start and value end of each option in the order they appear
+    options: Vec<(u8, usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    Strict,
+    // Option areas missing an end option are accepted
+    Lenient,
+}
+
+// Offsets are from the start of the packet
+#[derive(Debug)]
+pub enum PacketParseError {
+    TooShort(usize),
+    NoEndOption(usize),
+    InvalidMessageType(u8),
+    ParseOptionError(usize, ParseOptionError),
+    InvalidOverload(usize, u8),
+}
+
+// Offsets of the fields in a packet
+pub const SERVER_NAME_OFFSET: usize = 44;
+pub const BOOT_FILE_OFFSET: usize = 108;
+pub const MAGIC_OFFSET: usize = 236;
+pub const OPTIONS_OFFSET: usize = 240;
+
+pub const MAGIC: [u8; 4] = [99, 130, 83, 99];
+
+pub const OVERLOAD_BOOT_FILE: u8 = 1;
+pub const OVERLOAD_SERVER_NAME: u8 = 2;
+const OVERLOAD_BOTH: u8 = 3;
+
+impl<'a> DHCPPacketView<'a> {
+    pub fn parse(packet: &'a [u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        if packet.len() < MAGIC_OFFSET {
+            return Err(PacketParseError::TooShort(packet.len()));
+        }
+
+        // BOOTP clients may leave out the vendor area or use it for
+        // something other than options (RFC 1497), it is ignored unless it
+        // starts with the magic value
+        let vendor_extensions =
+            packet.len() >= OPTIONS_OFFSET && packet[MAGIC_OFFSET..OPTIONS_OFFSET] == MAGIC;
+
+        let message_type = match MessageType::parse(packet[0]) {
+            Some(message_type) => message_type,
+            None => return Err(PacketParseError::InvalidMessageType(packet[0])),
+        };
+
+        // Locate options
+        let mut options = Vec::new();
+        if vendor_extensions {
+            index_options(packet, OPTIONS_OFFSET, packet.len(), mode, &mut options)?;
+        }
+
+        // Locate options overloaded into the boot file and server name
+        // fields, the boot file field comes first (RFC 2131 section 4.1)
+        let overload_code = DHCPOptionClass::Overload.generate();
+        let mut overload = 0;
+        for (code, start, end) in &options {
+            if *code == overload_code {
+                overload = match packet[*start..*end].first() {
+                    Some(overload) => *overload,
+                    None => 0,
+                };
+
+                match overload {
+                    OVERLOAD_BOOT_FILE | OVERLOAD_SERVER_NAME | OVERLOAD_BOTH => break,
+                    _ => return Err(PacketParseError::InvalidOverload(start - 2, overload)),
+                }
+            }
+        }
+        options.retain(|(code, _, _)| *code != overload_code);
+
+        if overload & OVERLOAD_BOOT_FILE != 0 {
+            index_options(packet, BOOT_FILE_OFFSET, MAGIC_OFFSET, mode, &mut options)?;
+        }
+
+        if overload & OVERLOAD_SERVER_NAME != 0 {
+            index_options(
+                packet,
+                SERVER_NAME_OFFSET,
+                BOOT_FILE_OFFSET,
+                mode,
+                &mut options,
+            )?;
+        }
+
+        Ok(DHCPPacketView {
+            packet,
+            message_type,
+            vendor_extensions,
+            overload,
+            options,
+        })
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        self.message_type
+    }
+
+    pub fn has_vendor_extensions(&self) -> bool {
+        self.vendor_extensions
+    }
+
+    pub fn hardware_type(&self) -> HardwareType {
+        HardwareType::parse(self.packet[1])
+    }
+
+    pub fn hardware_address_length(&self) -> u8 {
+        self.packet[2]
+    }
+
+    pub fn hops(&self) -> u8 {
+        self.packet[3]
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        crate::slice_to_u32(&self.packet[4..])
+    }
+
+    pub fn seconds(&self) -> u16 {
+        crate::slice_to_u16(&self.packet[8..])
+    }
+
+    pub fn flags(&self) -> u16 {
+        crate::slice_to_u16(&self.packet[10..])
+    }
+
+    pub fn client_ip_address(&self) -> IPAddress {
+        self.ip_address(12)
+    }
+
+    pub fn your_ip_address(&self) -> IPAddress {
+        self.ip_address(16)
+    }
+
+    pub fn server_ip_address(&self) -> IPAddress {
+        self.ip_address(20)
+    }
+
+    pub fn gateway_ip_address(&self) -> IPAddress {
+        self.ip_address(24)
+    }
+
+    // Lengths over the size of chaddr are cut short
+    pub fn client_hardware_address(&self) -> HardwareAddress {
+        let length = (self.hardware_address_length() as usize).min(SERVER_NAME_OFFSET - 28);
+        HardwareAddress::new(self.hardware_type(), &self.packet[28..28 + length])
+    }
+
+    // None if the field holds overloaded options
+    pub fn server_name(&self) -> Option<&'a [u8]> {
+        if self.overload & OVERLOAD_SERVER_NAME != 0 {
+            None
+        } else {
+            Some(&self.packet[SERVER_NAME_OFFSET..BOOT_FILE_OFFSET])
+        }
+    }
+
+    pub fn boot_file(&self) -> Option<&'a [u8]> {
+        if self.overload & OVERLOAD_BOOT_FILE != 0 {
+            None
+        } else {
+            Some(&self.packet[BOOT_FILE_OFFSET..MAGIC_OFFSET])
+        }
+    }
+
+    // Every instance of an option, long options are split over several
+    pub fn options(&self) -> impl Iterator<Item = (DHCPOptionClass, &'a [u8])> + '_ {
+        self.options
+            .iter()
+            .map(|(code, start, end)| (DHCPOptionClass::parse(*code), &self.packet[*start..*end]))
+    }
+
+    // Only long options which are split over several instances need to be
+    // copied to be joined (RFC 3396)
+    pub fn get_option(&self, option_class: DHCPOptionClass) -> Option<Cow<'a, [u8]>> {
+        let code = option_class.generate();
+
+        let mut value: Option<Cow<'a, [u8]>> = None;
+        for (option_code, start, end) in &self.options {
+            if *option_code != code {
+                continue;
+            }
+
+            let segment = &self.packet[*start..*end];
+            value = Some(match value {
+                Some(value) => {
+                    let mut value = value.into_owned();
+                    value.extend_from_slice(segment);
+                    Cow::Owned(value)
+                }
+                None => Cow::Borrowed(segment),
+            });
+        }
+
+        value
+    }
+
+    pub fn get_value(
+        &self,
+        option_class: DHCPOptionClass,
+    ) -> Result<Option<OptionValue>, OptionValueError> {
+        match self.get_option(option_class) {
+            Some(value) => OptionValue::decode(option_class.descriptor(), &value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn ip_address(&self, offset: usize) -> IPAddress {
+        IPAddress::new([
+            self.packet[offset],
+            self.packet[offset + 1],
+            self.packet[offset + 2],
+            self.packet[offset + 3],
+        ])
+    }
+}
+
+// Locates the options in packet[start..end]
+fn index_options(
+    packet: &[u8],
+    start: usize,
+    end: usize,
+    mode: ParseMode,
+    options: &mut Vec<(u8, usize, usize)>,
+) -> Result<(), PacketParseError> {
+    let mut i = start;
+    while i < end {
+        // Pad and end are a single byte without a length
+        match DHCPOptionClass::parse(packet[i]) {
+            DHCPOptionClass::End => return Ok(()),
+            DHCPOptionClass::Pad => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if i + 1 >= end {
+            return Err(PacketParseError::ParseOptionError(
+                i,
+                ParseOptionError::MissingLength,
+            ));
+        }
+
+        let length = packet[i + 1] as usize;
+        if i + 2 + length > end {
+            return Err(PacketParseError::ParseOptionError(
+                i,
+                ParseOptionError::InvalidLength,
+            ));
+        }
+
+        options.push((packet[i], i + 2, i + 2 + length));
+        i += 2 + length;
+    }
+
+    match mode {
+        ParseMode::Strict => Err(PacketParseError::NoEndOption(end)),
+        ParseMode::Lenient => Ok(()),
+    }
+}
+
+// Options are shown as they appear, long options are not joined
+impl<'a> std::fmt::Display for DHCPPacketView<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Message Type: {}", self.message_type())?;
+        writeln!(f, "Hardware Type: {}", self.hardware_type())?;
+        writeln!(
+            f,
+            "Hardware Address Length: {}",
+            self.hardware_address_length()
+        )?;
+        writeln!(f, "Hops: {}", self.hops())?;
+        writeln!(f, "Transaction ID: {}", self.transaction_id())?;
+        writeln!(f, "Seconds: {}", self.seconds())?;
+        writeln!(f, "Flags: {}", self.flags())?;
+        writeln!(f, "Client I.P. Address: {}", self.client_ip_address())?;
+        writeln!(f, "Your I.P. Address: {}", self.your_ip_address())?;
+        writeln!(f, "Server I.P. Address: {}", self.server_ip_address())?;
+        writeln!(f, "Gateway I.P. Address: {}", self.gateway_ip_address())?;
+        writeln!(
+            f,
+            "Client Hardware Address: {:?}",
+            self.client_hardware_address().as_slice()
+        )?;
+        if let Some(server_name) = self.server_name() {
+            writeln!(f, "Server Name: {}", super::field_to_string(server_name))?;
+        }
+        if let Some(boot_file) = self.boot_file() {
+            writeln!(f, "Boot File: {}", super::field_to_string(boot_file))?;
+        }
+        writeln!(f, "Options:")?;
+        for (option_class, value) in self.options() {
+            writeln!(f, "    {}", DHCPOption::new(option_class, value.to_vec()))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PacketParseError {}
+
+impl std::fmt::Display for PacketParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PacketParseError::TooShort(length) => format!("Too short ({})", length),
+                PacketParseError::NoEndOption(offset) =>
+                    format!("No end option before byte {}", offset),
+                PacketParseError::InvalidMessageType(message_type) =>
+                    format!("Invalid message type ({})", message_type),
+                PacketParseError::ParseOptionError(offset, error) =>
+                    format!("Unable to parse option at byte {} ({})", offset, error),
+                PacketParseError::InvalidOverload(offset, overload) =>
+                    format!("Invalid overload value at byte {} ({})", offset, overload),
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A request with the given vendor area
+    fn packet(vendor_area: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0; MAGIC_OFFSET];
+        packet[0] = 1;
+        packet[1] = 1;
+        packet[2] = 6;
+        packet[28..34].copy_from_slice(&[0, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        packet.extend_from_slice(vendor_area);
+        packet
+    }
+
+    fn options(vendor_area: &[u8]) -> Vec<u8> {
+        let mut options = MAGIC.to_vec();
+        options.extend_from_slice(vendor_area);
+        options
+    }
+
+    #[test]
+    fn parse_skips_pad_options() {
+        let bytes = packet(&options(&[0, 0, 53, 1, 1, 0, 12, 2, b'p', b'c', 255]));
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+
+        let codes: Vec<DHCPOptionClass> = view.options().map(|(class, _)| class).collect();
+        assert_eq!(
+            codes,
+            [DHCPOptionClass::DHCPMsgType, DHCPOptionClass::Hostname]
+        );
+        assert_eq!(
+            view.client_hardware_address().as_slice(),
+            &[0, 0x11, 0x22, 0x33, 0x44, 0x55]
+        );
+    }
+
+    #[test]
+    fn strict_requires_end_option() {
+        let bytes = packet(&options(&[53, 1, 1, 0, 0]));
+
+        match DHCPPacketView::parse(&bytes, ParseMode::Strict) {
+            Err(PacketParseError::NoEndOption(end)) => assert_eq!(end, bytes.len()),
+            result => panic!("Expected no end option, got {:?}", result.err()),
+        }
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            view.get_option(DHCPOptionClass::DHCPMsgType).as_deref(),
+            Some(&[1][..])
+        );
+    }
+
+    #[test]
+    fn truncated_options_are_rejected_in_both_modes() {
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let bytes = packet(&options(&[53, 1, 1, 12, 5, b'p']));
+            match DHCPPacketView::parse(&bytes, mode) {
+                Err(PacketParseError::ParseOptionError(
+                    offset,
+                    ParseOptionError::InvalidLength,
+                )) => {
+                    assert_eq!(offset, OPTIONS_OFFSET + 3)
+                }
+                result => panic!("Expected an invalid length, got {:?}", result.err()),
+            }
+
+            let bytes = packet(&options(&[53, 1, 1, 12]));
+            match DHCPPacketView::parse(&bytes, mode) {
+                Err(PacketParseError::ParseOptionError(
+                    offset,
+                    ParseOptionError::MissingLength,
+                )) => {
+                    assert_eq!(offset, OPTIONS_OFFSET + 3)
+                }
+                result => panic!("Expected a missing length, got {:?}", result.err()),
+            }
+        }
+    }
+
+    #[test]
+    fn short_and_invalid_packets_are_rejected() {
+        match DHCPPacketView::parse(&[1; 100], ParseMode::Lenient) {
+            Err(PacketParseError::TooShort(100)) => {}
+            result => panic!("Expected too short, got {:?}", result.err()),
+        }
+
+        let mut bytes = packet(&options(&[255]));
+        bytes[0] = 7;
+        match DHCPPacketView::parse(&bytes, ParseMode::Lenient) {
+            Err(PacketParseError::InvalidMessageType(7)) => {}
+            result => panic!("Expected an invalid message type, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn vendor_area_without_magic_is_ignored() {
+        // BOOTP clients may use the vendor area for anything
+        let bytes = packet(&[1, 2, 3, 4, 5]);
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert!(!view.has_vendor_extensions());
+        assert_eq!(view.options().count(), 0);
+
+        let bytes = packet(&[]);
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert!(!view.has_vendor_extensions());
+    }
+
+    #[test]
+    fn overloaded_fields_are_parsed() {
+        let mut bytes = packet(&options(&[52, 1, 3, 53, 1, 1, 255]));
+        bytes[BOOT_FILE_OFFSET..BOOT_FILE_OFFSET + 5].copy_from_slice(&[12, 2, b'p', b'c', 255]);
+        bytes[SERVER_NAME_OFFSET..SERVER_NAME_OFFSET + 4].copy_from_slice(&[12, 1, b'1', 255]);
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+        assert!(view.boot_file().is_none());
+        assert!(view.server_name().is_none());
+
+        // The file field comes before the sname field and the overload
+        // option itself is left out
+        let codes: Vec<DHCPOptionClass> = view.options().map(|(class, _)| class).collect();
+        assert_eq!(
+            codes,
+            [
+                DHCPOptionClass::DHCPMsgType,
+                DHCPOptionClass::Hostname,
+                DHCPOptionClass::Hostname
+            ]
+        );
+        assert_eq!(
+            view.get_option(DHCPOptionClass::Hostname).as_deref(),
+            Some(&b"pc1"[..])
+        );
+    }
+
+    #[test]
+    fn overloaded_fields_need_an_end_option_in_strict_mode() {
+        let mut bytes = packet(&options(&[52, 1, 1, 255]));
+        bytes[BOOT_FILE_OFFSET..BOOT_FILE_OFFSET + 3].copy_from_slice(&[12, 1, b'p']);
+
+        match DHCPPacketView::parse(&bytes, ParseMode::Strict) {
+            Err(PacketParseError::NoEndOption(end)) => assert_eq!(end, MAGIC_OFFSET),
+            result => panic!("Expected no end option, got {:?}", result.err()),
+        }
+
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            view.get_option(DHCPOptionClass::Hostname).as_deref(),
+            Some(&b"p"[..])
+        );
+    }
+
+    #[test]
+    fn invalid_overload_is_rejected() {
+        let bytes = packet(&options(&[52, 1, 4, 255]));
+        match DHCPPacketView::parse(&bytes, ParseMode::Lenient) {
+            Err(PacketParseError::InvalidOverload(offset, 4)) => assert_eq!(offset, OPTIONS_OFFSET),
+            result => panic!("Expected an invalid overload, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn repeated_options_are_joined() {
+        let bytes = packet(&options(&[12, 2, b'p', b'c', 53, 1, 1, 12, 1, b'1', 255]));
+        let view = DHCPPacketView::parse(&bytes, ParseMode::Strict).unwrap();
+
+        // Only options split over several instances are copied
+        assert!(matches!(
+            view.get_option(DHCPOptionClass::DHCPMsgType),
+            Some(Cow::Borrowed(_))
+        ));
+        assert_eq!(
+            view.get_option(DHCPOptionClass::Hostname).as_deref(),
+            Some(&b"pc1"[..])
+        );
+        assert!(view.get_option(DHCPOptionClass::DomainName).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     collections::VecDeque,
     sync::{Mutex, Once},
+    time::SystemTime,
 };
 
 struct ServerContainer(Option<Mutex<Server>>);
@@ -29,12 +30,29 @@ fn log_server_error(error: std::io::Error) {
     );
 }
 
+// Relay agent information and log messages hold text sent by clients and
+// relay agents which must not be read as HTML
+fn escape_html(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for c in str.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 static SERVER_INIT: Once = Once::new();
 static mut SERVER: ServerContainer = ServerContainer(None);
 
 pub fn start(configuration: &crate::config::Configuration) {
     // Generate the configuration body
-    let mut body = format!("<html>");
+    let mut body = "<html>".to_owned();
     body.push_str("<head>");
     body.push_str("<title>DHCP Server</title>");
     body.push_str("<meta name='viewport' content='width=device-width, initial-scale=1.0'>");
@@ -46,14 +64,6 @@ pub fn start(configuration: &crate::config::Configuration) {
     body.push_str("<h1>DHCP Server</h1>");
     body.push_str("<h2>Configuration</h2>");
     body.push_str("<h3>Lease Configuration</h3>");
-    body.push_str(&format!(
-        "<b>Starting IP Address:</b> {}<br />",
-        configuration.lease_start_ip()
-    ));
-    body.push_str(&format!(
-        "<b>Final IP Address:</b> {}<br />",
-        configuration.lease_final_ip()
-    ));
     body.push_str(&format!(
         "<b>Lease Time:</b> {} seconds<br />",
         configuration.address_time()
@@ -70,36 +80,85 @@ pub fn start(configuration: &crate::config::Configuration) {
         "<b>Offer Time:</b> {} seconds<br />",
         configuration.offer_time()
     ));
+    body.push_str(&format!(
+        "<b>Decline Quarantine Time:</b> {} seconds<br />",
+        configuration.decline_time()
+    ));
+    if !configuration.excluded_ips().is_empty() {
+        body.push_str("<b>Excluded IP Addresses:</b>");
+        body.push_str("<table>");
+        body.push_str("<tr><th>Start</th><th>Final</th></tr>");
+        for (start, end) in configuration.excluded_ips() {
+            body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", start, end));
+        }
+        body.push_str("</table>");
+    }
     body.push_str("<h3>Network Configuration</h3>");
     body.push_str(&format!(
         "<b>Our IP Address:</b> {}<br />",
         configuration.our_ip()
     ));
-    body.push_str(&format!(
-        "<b>Gateway IP Address:</b> {}<br />",
-        configuration.gateway_ip()
-    ));
-    body.push_str(&format!(
-        "<b>Subnet Mask:</b> {}<br />",
-        configuration.subnet_mask()
-    ));
-    body.push_str(&format!(
-        "<b>Broadcast IP Address:</b> {}<br />",
-        configuration.broadcast_address()
-    ));
+    body.push_str("<b>Subnets:</b>");
+    body.push_str("<table>");
+    body.push_str(
+        "<tr><th>Subnet</th><th>Shared Network</th><th>Gateway</th><th>Broadcast</th><th>Pools</th><th>Options</th></tr>",
+    );
+    for subnet in configuration.subnets() {
+        let pools: Vec<String> = subnet
+            .pools()
+            .iter()
+            .map(|(start, end)| format!("{} - {}", start, end))
+            .collect();
+        let options: Vec<String> = subnet
+            .options()
+            .iter()
+            .map(|(option_class, value)| format!("{}: {}", option_class, value))
+            .collect();
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            subnet,
+            subnet.shared_network().unwrap_or_default(),
+            match subnet.gateway_ip() {
+                Some(gateway_ip) => gateway_ip.to_string(),
+                None => String::new(),
+            },
+            subnet.broadcast_address(),
+            pools.join("<br />"),
+            options.join("<br />")
+        ));
+    }
+    body.push_str("</table>");
     let (dns, dns_alternate) = configuration.dns();
     body.push_str(&format!("<b>D.N.S. Server:</b> {}<br />", dns));
     body.push_str(&format!(
         "<b>D.N.S. Alternative Server:</b> {}<br />",
         dns_alternate
     ));
+    if !configuration.options().is_empty() {
+        body.push_str("<b>Options:</b>");
+        body.push_str("<table>");
+        body.push_str("<tr><th>Code</th><th>Option</th><th>Value</th></tr>");
+        for (option_class, value) in configuration.options() {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                option_class.generate(),
+                option_class,
+                value
+            ));
+        }
+        body.push_str("</table>");
+    }
     body.push_str("<h2>Allocated IP Addresses</h2>");
     body.push_str("<h3>Reserved IP Addresses</h3>");
-    if configuration.reserved_ips().len() > 0 {
+    if !configuration.reservations().is_empty() {
         body.push_str("<table>");
-        body.push_str("<tr><th>IP Address</th><th>MAC Address</th></tr>");
-        for (mac, ip) in configuration.reserved_ips() {
-            body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", ip, mac));
+        body.push_str("<tr><th>IP Address</th><th>Reserved For</th></tr>");
+        for reservation in configuration.reservations() {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>",
+                reservation.ip_address(),
+                reservation
+            ));
         }
         body.push_str("</table>");
     } else {
@@ -137,27 +196,63 @@ impl Server {
         let mut body = self.configuration_body.clone();
 
         // Append current leases
-        let leases = unsafe { crate::DHCP_SERVER.as_ref() }
-            .unwrap()
-            .lock()
-            .unwrap()
-            .current_leases();
+        let (leases, declined) = {
+            let server = unsafe { crate::DHCP_SERVER.as_ref() }
+                .unwrap()
+                .lock()
+                .unwrap();
+            (server.current_leases(), server.current_declined())
+        };
 
         body.push_str("<h3>Leased IP Addresses</h3>");
-        if leases.len() > 0 {
+        if !leases.is_empty() {
             body.push_str("<table>");
-            body.push_str("<tr><th>IP Address</th><th>MAC Address</th></tr>");
-            for (ip, mac) in leases {
-                body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", ip, mac));
+            body.push_str(
+                "<tr><th>IP Address</th><th>Client</th><th>Relay Agent Information</th></tr>",
+            );
+            for (ip, client, relay_agent_information) in leases {
+                body.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    ip,
+                    client,
+                    match relay_agent_information {
+                        Some(relay_agent_information) =>
+                            escape_html(&relay_agent_information.to_string()),
+                        None => String::new(),
+                    }
+                ));
             }
             body.push_str("</table>");
         } else {
             body.push_str("No IP addresses are currently leased");
         }
 
+        body.push_str("<h3>Quarantined IP Addresses</h3>");
+        if !declined.is_empty() {
+            let now = SystemTime::now();
+            body.push_str("<table>");
+            body.push_str(
+                "<tr><th>IP Address</th><th>Declined By</th><th>Remaining Time</th></tr>",
+            );
+            for (ip, client, expiry) in declined {
+                body.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{} seconds</td></tr>",
+                    ip,
+                    client,
+                    match expiry.duration_since(now) {
+                        Ok(remaining) => remaining.as_secs(),
+                        Err(_) => 0,
+                    }
+                ));
+            }
+            body.push_str("</table>");
+        } else {
+            body.push_str("No IP addresses are currently quarantined");
+        }
+
         // Append logs
         body.push_str("<h2>Log</h2>");
-        if self.logs.len() > 0 {
+        if !self.logs.is_empty() {
             body.push_str("<table>");
             body.push_str(
                 "<tr><th>Date & Time</th><th>Severity</th><th>Source</th><th>Message</th></tr>",
@@ -165,7 +260,10 @@ impl Server {
             for (timestamp, level, name, message) in &self.logs {
                 body.push_str(&format!(
                     "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
-                    timestamp, level, name, message
+                    timestamp,
+                    level,
+                    name,
+                    escape_html(message)
                 ));
             }
             body.push_str("</table>");
@@ -179,7 +277,7 @@ impl Server {
         let mut response = http::Response::new_status(http::Status::Ok, Some(body));
         response
             .header_mut()
-            .insert_header(format!("Content-Type"), format!("text/html"));
+            .insert_header("Content-Type".to_owned(), "text/html".to_owned());
 
         response
     }
@@ -192,13 +290,10 @@ impl Server {
             record.message().to_owned(),
         ));
 
-        match self.log_limit {
-            Some(limit) => {
-                if self.logs.len() > limit {
-                    self.logs.pop_back();
-                }
+        if let Some(limit) = self.log_limit {
+            if self.logs.len() > limit {
+                self.logs.pop_back();
             }
-            None => {}
         }
     }
 }
@@ -214,7 +309,7 @@ impl http::Server for ServerContainer {
                     );
                     response
                         .header_mut()
-                        .insert_header(format!("Content-Type"), format!("text/css"));
+                        .insert_header("Content-Type".to_owned(), "text/css".to_owned());
                     response
                 } else {
                     self.0.as_ref().unwrap().lock().unwrap().handle_request()
@@ -1,25 +1,27 @@
-use std::{
-    net::UdpSocket,
-    sync::{Mutex, Once},
-};
+use std::sync::{Mutex, Once};
 
 mod address;
 mod config;
 mod dhcp;
 mod http_server;
 mod server;
+mod socket;
 mod util;
 
 pub use address::*;
 pub use util::*;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum RuntimeError {
     CreateServerError(std::io::Error),
+    UnknownInterface(String),
     LoadConfigurationError(config::ConfigurationError),
+    LoadLeasesError(server::LeaseDatabaseError),
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum RequestError {
     ReadRequestError(std::io::Error),
     ParsePacketError(dhcp::PacketParseError),
@@ -29,6 +31,9 @@ enum RequestError {
 
 const BROADCAST_ADDRESS: IPAddress = IPAddress::new([255, 255, 255, 255]);
 
+// Largest possible U.D.P. payload
+const MAX_DATAGRAM_SIZE: usize = 65535;
+
 static DHCP_SERVER_INIT: Once = Once::new();
 static mut DHCP_SERVER: Option<Mutex<server::DHCPServer>> = None;
 
@@ -71,25 +76,28 @@ fn run() -> Result<(), RuntimeError> {
     let configuration = config::load_configuration()?;
     logging::info!(logger, "Configuration loaded");
 
+    // Requests from other interfaces are ignored
+    let mut interfaces = Vec::new();
+    for name in configuration.interfaces() {
+        match socket::interface_index(name) {
+            Some(index) => interfaces.push(index),
+            None => return Err(RuntimeError::UnknownInterface(name.to_owned())),
+        }
+    }
+
     // Create DHCP Server
-    DHCP_SERVER_INIT.call_once(|| unsafe {
-        DHCP_SERVER = Some(Mutex::new(server::DHCPServer::new(&configuration)))
-    });
+    let dhcp_server = server::DHCPServer::new(&configuration)?;
+    DHCP_SERVER_INIT.call_once(|| unsafe { DHCP_SERVER = Some(Mutex::new(dhcp_server)) });
 
     // Create HTTP Server
     http_server::start(&configuration);
 
     // Create UDP Server
-    let mut socket = match UdpSocket::bind(format!("0.0.0.0:{}", server::DHCP_SERVER_PORT)) {
+    let socket = match socket::DHCPSocket::bind(server::DHCP_SERVER_PORT) {
         Ok(socket) => socket,
         Err(error) => return Err(RuntimeError::CreateServerError(error)),
     };
 
-    match socket.set_broadcast(true) {
-        Ok(()) => {}
-        Err(error) => return Err(RuntimeError::CreateServerError(error)),
-    };
-
     logging::info!(
         logger,
         "Server listening on port {}",
@@ -97,8 +105,16 @@ fn run() -> Result<(), RuntimeError> {
     );
 
     // Handle requests
+    let parse_mode = configuration.parse_mode();
+    let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
     loop {
-        match handle_request(&mut socket, unsafe { DHCP_SERVER.as_ref() }.unwrap()) {
+        match handle_request(
+            &socket,
+            &mut buffer,
+            &interfaces,
+            unsafe { DHCP_SERVER.as_ref() }.unwrap(),
+            parse_mode,
+        ) {
             Ok(()) => {}
             Err(error) => logging::error!(logger, "{}", error),
         }
@@ -106,38 +122,52 @@ fn run() -> Result<(), RuntimeError> {
 }
 
 fn handle_request(
-    socket: &mut UdpSocket,
+    socket: &socket::DHCPSocket,
+    buffer: &mut [u8],
+    interfaces: &[u32],
     server_lock: &Mutex<server::DHCPServer>,
+    parse_mode: dhcp::ParseMode,
 ) -> Result<(), RequestError> {
     // Read packet
-    let mut buffer = [0; 576];
-    let (packet_size, _) = match socket.recv_from(&mut buffer) {
+    let (packet_size, _, packet_info) = match socket.recv_from(buffer) {
         Ok(result) => result,
         Err(error) => return Err(RequestError::ReadRequestError(error)),
     };
 
+    if !interfaces.is_empty() {
+        match packet_info {
+            Some(packet_info) => {
+                if !interfaces.contains(&packet_info.interface_index()) {
+                    return Ok(());
+                }
+            }
+            None => return Ok(()),
+        }
+    }
+
     // Convert to correct size packet
     let buffer = &buffer[..packet_size];
 
-    // Parse packet
-    let packet = dhcp::DHCPPacket::parse(buffer)?;
+    // Parse packet, options are read in place
+    let packet = dhcp::DHCPPacketView::parse(buffer, parse_mode)?;
 
     // Handle packet
     let mut server = server_lock.lock().unwrap();
-    match server.handle_packet(packet)? {
-        Some((response_packet, target)) => {
-            match socket.send_to(
-                response_packet.generate().as_slice(),
-                match target {
-                    Some(target) => target,
-                    None => BROADCAST_ADDRESS.to_socket_addr(server::DHCP_CLIENT_PORT),
-                },
-            ) {
-                Ok(_) => {}
-                Err(error) => return Err(RequestError::WriteResponseError(error)),
-            }
+    if let Some((response_packet, target)) = server.handle_packet(
+        &packet,
+        packet_info.map(|packet_info| packet_info.local_address()),
+    )? {
+        match socket.send_to(
+            response_packet.generate().as_slice(),
+            match target {
+                Some(target) => target,
+                None => BROADCAST_ADDRESS.to_socket_addr(server::DHCP_CLIENT_PORT),
+            },
+            packet_info,
+        ) {
+            Ok(_) => {}
+            Err(error) => return Err(RequestError::WriteResponseError(error)),
         }
-        None => {}
     }
 
     Ok(())
@@ -153,8 +183,11 @@ impl std::fmt::Display for RuntimeError {
             match self {
                 RuntimeError::CreateServerError(error) =>
                     format!("Unable to create server ({})", error),
+                RuntimeError::UnknownInterface(name) => format!("Unknown interface \"{}\"", name),
                 RuntimeError::LoadConfigurationError(error) =>
                     format!("Error while loading configuration - {}", error),
+                RuntimeError::LoadLeasesError(error) =>
+                    format!("Error while loading leases - {}", error),
             }
         )
     }
@@ -196,3 +229,9 @@ impl From<config::ConfigurationError> for RuntimeError {
         RuntimeError::LoadConfigurationError(error)
     }
 }
+
+impl From<server::LeaseDatabaseError> for RuntimeError {
+    fn from(error: server::LeaseDatabaseError) -> Self {
+        RuntimeError::LoadLeasesError(error)
+    }
+}
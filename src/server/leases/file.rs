@@ -0,0 +1,498 @@
+use super::{LeaseDatabaseError, LeaseStore, MemoryLeaseStore};
+use crate::{
+    config::Subnet,
+    dhcp::{DHCPOptionClass, OptionValue, RelayAgentInformation},
+    ClientID, IPAddress,
+};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Leases are kept in memory and every change is appended to a journal. Each
+// line of the journal is one of:
+//  lease <I.P. address> <client> <expiry in seconds since the UNIX epoch> [relay agent information in hex]
+//  release <I.P. address>
+//  decline <I.P. address> <client> <end of quarantine in seconds since the UNIX epoch>
+// Clients are written as "mac:" and their MAC address if they are identified
+// by one and as their client identifier in hex otherwise. The journal is
+// compacted down to the active leases every time it is opened and whenever
+// more records have been appended than it held after the last compaction.
+pub struct FileLeaseStore {
+    memory: MemoryLeaseStore,
+    path: PathBuf,
+    file: File,
+    // Records written by the last compaction and appended since
+    compacted_records: usize,
+    appended_records: usize,
+}
+
+// Small journals aren't worth compacting
+const MIN_COMPACTION_RECORDS: usize = 1024;
+
+impl FileLeaseStore {
+    pub fn open(configuration: &crate::config::Configuration) -> Result<Self, LeaseDatabaseError> {
+        let logger = logging::get_logger(module_path!());
+
+        let path = configuration.lease_file().to_owned();
+        let mut memory = MemoryLeaseStore::new(configuration);
+
+        // Replay the journal
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                for (i, line) in contents.lines().enumerate() {
+                    if !replay_record(&mut memory, line) {
+                        return Err(LeaseDatabaseError::InvalidRecord(path, i + 1));
+                    }
+                }
+            }
+            Err(error) => match error.kind() {
+                std::io::ErrorKind::NotFound => {}
+                _ => return Err(LeaseDatabaseError::ReadError(path, error)),
+            },
+        }
+
+        // Compact the journal
+        let (file, compacted_records) = match compact(&memory, &path) {
+            Ok(result) => result,
+            Err(error) => return Err(LeaseDatabaseError::WriteError(path, error)),
+        };
+
+        logging::info!(
+            logger,
+            "Loaded {} leases from {}",
+            memory.leases().count(),
+            path.display()
+        );
+
+        Ok(FileLeaseStore {
+            memory,
+            path,
+            file,
+            compacted_records,
+            appended_records: 0,
+        })
+    }
+
+    fn append(&mut self, record: String) {
+        let logger = logging::get_logger(module_path!());
+
+        match self
+            .file
+            .write_all(record.as_bytes())
+            .and_then(|()| self.file.sync_data())
+        {
+            Ok(()) => {}
+            Err(error) => logging::error!(
+                logger,
+                "Unable to write to lease database {} ({})",
+                self.path.display(),
+                error
+            ),
+        }
+
+        // Every renewal adds a record, so a long running server compacts the
+        // journal once it has grown to twice its compacted size
+        self.appended_records += 1;
+        if self.appended_records < self.compacted_records.max(MIN_COMPACTION_RECORDS) {
+            return;
+        }
+
+        match compact(&self.memory, &self.path) {
+            Ok((file, compacted_records)) => {
+                self.file = file;
+                self.compacted_records = compacted_records;
+                self.appended_records = 0;
+            }
+            Err(error) => logging::error!(
+                logger,
+                "Unable to compact lease database {} ({})",
+                self.path.display(),
+                error
+            ),
+        }
+    }
+}
+
+impl LeaseStore for FileLeaseStore {
+    fn clean_leases(&mut self) {
+        // Expired leases are dropped when the journal is replayed
+        self.memory.clean_leases()
+    }
+
+    fn allocate(&mut self, client_id: &ClientID, subnet: &Subnet) -> Option<IPAddress> {
+        self.memory.allocate(client_id, subnet)
+    }
+
+    fn accept_offer(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: &ClientID,
+        relay_agent_information: Option<&RelayAgentInformation>,
+        address_time: u32,
+    ) -> bool {
+        if !self
+            .memory
+            .accept_offer(ip_address, client_id, relay_agent_information, address_time)
+        {
+            return false;
+        }
+
+        let record =
+            self.memory
+                .lease(ip_address)
+                .map(|(client, expiry, relay_agent_information)| {
+                    format_record("lease", ip_address, client, expiry, relay_agent_information)
+                });
+
+        if let Some(record) = record {
+            self.append(record);
+        }
+
+        true
+    }
+
+    fn cancel_offer(&mut self, client_id: &ClientID) {
+        self.memory.cancel_offer(client_id)
+    }
+
+    fn get_ip_address(&self, client_id: &ClientID) -> Option<IPAddress> {
+        self.memory.get_ip_address(client_id)
+    }
+
+    fn release(&mut self, ip_address: IPAddress, client_id: &ClientID) {
+        let held = match self.memory.lease(ip_address) {
+            Some((client, _, _)) => client == client_id,
+            None => false,
+        };
+
+        if held {
+            self.memory.release(ip_address, client_id);
+            self.append(format!("release {}\n", ip_address));
+        }
+    }
+
+    fn decline(&mut self, ip_address: IPAddress, client_id: &ClientID) -> bool {
+        if !self.memory.decline(ip_address, client_id) {
+            return false;
+        }
+
+        let record = self
+            .memory
+            .declined(ip_address)
+            .map(|(client, expiry)| format_record("decline", ip_address, client, expiry, None));
+
+        if let Some(record) = record {
+            self.append(record);
+        }
+
+        true
+    }
+
+    fn current_leases(&self) -> Vec<(IPAddress, ClientID, Option<RelayAgentInformation>)> {
+        self.memory.current_leases()
+    }
+
+    fn current_declined(&self) -> Vec<(IPAddress, ClientID, SystemTime)> {
+        self.memory.current_declined()
+    }
+}
+
+// Writes the active leases and quarantined addresses to a new journal which
+// replaces the old one, so a failure never leaves a partial journal. Returns
+// the new journal to append to and the number of records in it.
+fn compact(memory: &MemoryLeaseStore, path: &Path) -> std::io::Result<(File, usize)> {
+    let mut temporary_path = path.to_owned().into_os_string();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let mut contents = String::new();
+    let mut records = 0;
+    for (ip, client, expiry, relay_agent_information) in memory.leases() {
+        contents.push_str(&format_record(
+            "lease",
+            ip,
+            client,
+            expiry,
+            relay_agent_information,
+        ));
+        records += 1;
+    }
+    for (ip, client, expiry) in memory.current_declined() {
+        contents.push_str(&format_record("decline", ip, &client, expiry, None));
+        records += 1;
+    }
+
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temporary_path, path)?;
+
+    // The file is still open at the end of the records, so later records are
+    // appended to the new journal
+    Ok((file, records))
+}
+
+fn format_record(
+    record: &str,
+    ip_address: IPAddress,
+    client_id: &ClientID,
+    expiry: SystemTime,
+    relay_agent_information: Option<&RelayAgentInformation>,
+) -> String {
+    format!(
+        "{} {} {} {}{}\n",
+        record,
+        ip_address,
+        client_id,
+        match expiry.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        },
+        match relay_agent_information {
+            Some(relay_agent_information) => format!(
+                " {}",
+                OptionValue::Binary(relay_agent_information.generate())
+            ),
+            None => String::new(),
+        }
+    )
+}
+
+fn replay_record(memory: &mut MemoryLeaseStore, line: &str) -> bool {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+
+    match parts.first() {
+        None => true,
+        Some(&"lease") | Some(&"decline") => {
+            if parts.len() != 4 && !(parts[0] == "lease" && parts.len() == 5) {
+                return false;
+            }
+
+            let ip = match IPAddress::parse(parts[1]) {
+                Some(ip) => ip,
+                None => return false,
+            };
+
+            let client = match ClientID::parse(parts[2]) {
+                Some(client) => client,
+                None => return false,
+            };
+
+            let expiry = match parts[3].parse() {
+                Ok(seconds) => UNIX_EPOCH + Duration::from_secs(seconds),
+                Err(_) => return false,
+            };
+
+            let relay_agent_information = match parts.get(4) {
+                Some(value) => match OptionValue::parse(
+                    DHCPOptionClass::RelayAgentInformation.descriptor(),
+                    value,
+                ) {
+                    Some(value) => match value.as_binary() {
+                        Some(value) => match RelayAgentInformation::parse(value) {
+                            Ok(relay_agent_information) => Some(relay_agent_information),
+                            Err(_) => return false,
+                        },
+                        None => return false,
+                    },
+                    None => return false,
+                },
+                None => None,
+            };
+
+            if parts[0] == "lease" {
+                memory.restore_lease(ip, client, expiry, relay_agent_information);
+            } else {
+                memory.restore_declined(ip, client, expiry);
+            }
+            true
+        }
+        Some(&"release") => {
+            if parts.len() != 2 {
+                return false;
+            }
+
+            match IPAddress::parse(parts[1]) {
+                Some(ip) => {
+                    memory.remove_lease(ip);
+                    true
+                }
+                None => false,
+            }
+        }
+        Some(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IPAddress {
+        IPAddress::new([192, 168, 1, last])
+    }
+
+    fn ethernet(last: u8) -> ClientID {
+        ClientID::from_hardware_address(1, &[0, 0x11, 0x22, 0x33, 0x44, last])
+    }
+
+    fn memory() -> MemoryLeaseStore {
+        MemoryLeaseStore::new(&crate::config::Configuration::test(PathBuf::new()))
+    }
+
+    fn in_an_hour() -> u64 {
+        (SystemTime::now() + Duration::from_secs(60 * 60))
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    // Each test uses its own journal which is removed when it is dropped
+    struct Journal(PathBuf);
+
+    impl Journal {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("dhcp-server-{}-{}", std::process::id(), name));
+            let _ = std::fs::remove_file(&path);
+            Journal(path)
+        }
+
+        fn open(&self) -> FileLeaseStore {
+            FileLeaseStore::open(&crate::config::Configuration::test(self.0.clone())).unwrap()
+        }
+
+        fn contents(&self) -> String {
+            std::fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for Journal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn replay_leases_releases_and_declines() {
+        let mut memory = memory();
+        let expiry = in_an_hour();
+
+        assert!(replay_record(
+            &mut memory,
+            &format!(
+                "lease 192.168.1.10 mac:00:11:22:33:44:55 {} 01:03:65:74:68",
+                expiry
+            )
+        ));
+        assert!(replay_record(
+            &mut memory,
+            &format!("lease 192.168.1.11 FF00AA {}", expiry)
+        ));
+        assert!(replay_record(
+            &mut memory,
+            &format!("decline 192.168.1.12 mac:00:11:22:33:44:57 {}", expiry)
+        ));
+        assert!(replay_record(&mut memory, "release 192.168.1.11"));
+        assert!(replay_record(&mut memory, ""));
+
+        let (client, _, relay_agent_information) = memory.lease(ip(10)).unwrap();
+        assert_eq!(*client, ethernet(0x55));
+        assert_eq!(
+            relay_agent_information.and_then(|information| information.circuit_id()),
+            Some(&b"eth"[..])
+        );
+        assert!(memory.lease(ip(11)).is_none());
+        assert_eq!(*memory.declined(ip(12)).unwrap().0, ethernet(0x57));
+    }
+
+    #[test]
+    fn replay_drops_expired_leases() {
+        let mut memory = memory();
+        assert!(replay_record(
+            &mut memory,
+            "lease 192.168.1.10 mac:00:11:22:33:44:55 1"
+        ));
+        assert!(memory.lease(ip(10)).is_none());
+    }
+
+    #[test]
+    fn replay_rejects_invalid_records() {
+        let expiry = in_an_hour();
+        for record in [
+            format!("lease 192.168.1 mac:00:11:22:33:44:55 {}", expiry),
+            format!("lease 192.168.1.10 mac:00:11 {}", expiry),
+            format!(
+                "lease 192.168.1.10 mac:00:11:22:33:44:55 {} 01:05:65",
+                expiry
+            ),
+            format!(
+                "decline 192.168.1.10 mac:00:11:22:33:44:55 {} 01:00",
+                expiry
+            ),
+            "lease 192.168.1.10 mac:00:11:22:33:44:55 soon".to_owned(),
+            "lease 192.168.1.10 mac:00:11:22:33:44:55".to_owned(),
+            "release 192.168.1.10 mac:00:11:22:33:44:55".to_owned(),
+            "renew 192.168.1.10".to_owned(),
+        ] {
+            assert!(!replay_record(&mut memory(), &record), "{}", record);
+        }
+    }
+
+    #[test]
+    fn format_record_round_trips() {
+        let relay_agent_information = RelayAgentInformation::parse(&[2, 2, 0xAB, 0xCD]).unwrap();
+        let expiry = UNIX_EPOCH + Duration::from_secs(in_an_hour());
+        let record = format_record(
+            "lease",
+            ip(10),
+            &ClientID::new(vec![0xFF, 0, 0xAA]),
+            expiry,
+            Some(&relay_agent_information),
+        );
+
+        let mut memory = memory();
+        assert!(replay_record(&mut memory, record.trim_end()));
+        let (client, lease_expiry, lease_relay_agent_information) = memory.lease(ip(10)).unwrap();
+        assert_eq!(*client, ClientID::new(vec![0xFF, 0, 0xAA]));
+        assert_eq!(lease_expiry, expiry);
+        assert_eq!(
+            lease_relay_agent_information,
+            Some(&relay_agent_information)
+        );
+    }
+
+    #[test]
+    fn leases_survive_reopening() {
+        let journal = Journal::new("reopen");
+
+        {
+            let mut store = journal.open();
+            let subnet = &crate::config::Configuration::test(PathBuf::new()).subnets()[0].clone();
+            let first = store.allocate(&ethernet(1), subnet).unwrap();
+            assert!(store.accept_offer(first, &ethernet(1), None, 3600));
+            let second = store.allocate(&ethernet(2), subnet).unwrap();
+            assert!(store.accept_offer(second, &ethernet(2), None, 3600));
+            store.release(second, &ethernet(2));
+
+            // Only the client holding an address may decline it
+            let third = store.allocate(&ethernet(3), subnet).unwrap();
+            assert!(store.accept_offer(third, &ethernet(3), None, 3600));
+            assert!(!store.decline(third, &ethernet(4)));
+            assert!(!store.decline(ip(20), &ethernet(4)));
+            assert!(store.decline(third, &ethernet(3)));
+            assert!(!store.decline(third, &ethernet(4)));
+        }
+
+        let store = journal.open();
+        assert_eq!(store.get_ip_address(&ethernet(1)), Some(ip(2)));
+        assert_eq!(store.get_ip_address(&ethernet(2)), None);
+        assert_eq!(store.current_declined().len(), 1);
+
+        // Opening compacts the journal down to the active records
+        assert_eq!(journal.contents().lines().count(), 2);
+    }
+}
@@ -0,0 +1,339 @@
+use super::{AddressPool, LeaseStore};
+use crate::{config::Subnet, dhcp::RelayAgentInformation, ClientID, IPAddress};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime},
+};
+
+pub struct MemoryLeaseStore {
+    leases: HashMap<IPAddress, (ClientID, SystemTime, Option<RelayAgentInformation>)>,
+    offers: HashMap<IPAddress, (ClientID, SystemTime)>,
+    declined: HashMap<IPAddress, (ClientID, SystemTime)>,
+    pool: AddressPool,
+    reserved: HashSet<IPAddress>,
+    offer_time: u64,
+    decline_time: u64,
+}
+
+impl MemoryLeaseStore {
+    pub fn new(configuration: &crate::config::Configuration) -> Self {
+        let mut pool = AddressPool::new();
+        for subnet in configuration.subnets() {
+            pool.add_subnet(subnet.network(), subnet.last_address());
+            for (start, end) in subnet.pools() {
+                pool.add_range(*start, *end);
+            }
+        }
+
+        // Reserved and excluded addresses are never handed out dynamically
+        let mut reserved = HashSet::new();
+        for reservation in configuration.reservations() {
+            pool.exclude(reservation.ip_address(), reservation.ip_address());
+            reserved.insert(reservation.ip_address());
+        }
+
+        for (start, end) in configuration.excluded_ips() {
+            pool.exclude(*start, *end);
+        }
+
+        MemoryLeaseStore {
+            leases: HashMap::new(),
+            offers: HashMap::new(),
+            declined: HashMap::new(),
+            pool,
+            reserved,
+            offer_time: configuration.offer_time(),
+            decline_time: configuration.decline_time(),
+        }
+    }
+
+    pub fn lease(
+        &self,
+        ip_address: IPAddress,
+    ) -> Option<(&ClientID, SystemTime, Option<&RelayAgentInformation>)> {
+        self.leases
+            .get(&ip_address)
+            .map(|(client, expiry, relay_agent_information)| {
+                (client, *expiry, relay_agent_information.as_ref())
+            })
+    }
+
+    pub fn leases(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            IPAddress,
+            &ClientID,
+            SystemTime,
+            Option<&RelayAgentInformation>,
+        ),
+    > + '_ {
+        self.leases
+            .iter()
+            .map(|(ip, (client, expiry, relay_agent_information))| {
+                (*ip, client, *expiry, relay_agent_information.as_ref())
+            })
+    }
+
+    pub fn declined(&self, ip_address: IPAddress) -> Option<(&ClientID, SystemTime)> {
+        self.declined
+            .get(&ip_address)
+            .map(|(client, expiry)| (client, *expiry))
+    }
+
+    // Used by persistent stores to restore leases, expired leases are ignored
+    pub fn restore_lease(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: ClientID,
+        expiry: SystemTime,
+        relay_agent_information: Option<RelayAgentInformation>,
+    ) {
+        if expiry > SystemTime::now() {
+            self.pool.take(ip_address);
+            self.declined.remove(&ip_address);
+            self.leases
+                .insert(ip_address, (client_id, expiry, relay_agent_information));
+        }
+    }
+
+    pub fn restore_declined(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: ClientID,
+        expiry: SystemTime,
+    ) {
+        if expiry > SystemTime::now() {
+            self.pool.take(ip_address);
+            self.leases.remove(&ip_address);
+            self.declined.insert(ip_address, (client_id, expiry));
+        }
+    }
+
+    pub fn remove_lease(&mut self, ip_address: IPAddress) {
+        if self.leases.remove(&ip_address).is_some() {
+            self.pool.free(ip_address);
+        }
+    }
+}
+
+impl LeaseStore for MemoryLeaseStore {
+    fn clean_leases(&mut self) {
+        let logger = logging::get_logger(module_path!());
+        let now = SystemTime::now();
+
+        let pool = &mut self.pool;
+
+        self.offers.retain(|ip, (client, expiry)| {
+            if *expiry > now {
+                true
+            } else {
+                logging::info!(
+                    logger,
+                    "Removed offer for {} to {} due to expiry",
+                    ip,
+                    client
+                );
+                pool.free(*ip);
+                false
+            }
+        });
+
+        self.leases.retain(|ip, (client, expiry, _)| {
+            if *expiry > now {
+                true
+            } else {
+                logging::info!(
+                    logger,
+                    "Removed lease for {} to {} due to expiry",
+                    ip,
+                    client
+                );
+                pool.free(*ip);
+                false
+            }
+        });
+
+        self.declined.retain(|ip, (client, expiry)| {
+            if *expiry > now {
+                true
+            } else {
+                logging::info!(
+                    logger,
+                    "Returned {} declined by {} to the pool after quarantine",
+                    ip,
+                    client
+                );
+                pool.free(*ip);
+                false
+            }
+        });
+    }
+
+    fn allocate(&mut self, client_id: &ClientID, subnet: &Subnet) -> Option<IPAddress> {
+        let ip_address = self
+            .pool
+            .allocate(subnet.network(), subnet.last_address())?;
+
+        // Reserve the offer
+        self.offers.insert(
+            ip_address,
+            (
+                client_id.clone(),
+                SystemTime::now() + Duration::from_secs(self.offer_time),
+            ),
+        );
+
+        Some(ip_address)
+    }
+
+    fn accept_offer(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: &ClientID,
+        relay_agent_information: Option<&RelayAgentInformation>,
+        address_time: u32,
+    ) -> bool {
+        // Reserved addresses aren't in the pool, the server has already
+        // matched the client to the reservation so the binding is recorded to
+        // know who holds the address
+        if !self.reserved.contains(&ip_address) {
+            // Verify I.P. is part of the pool
+            if !self.pool.contains(ip_address) {
+                return false;
+            }
+
+            // Check offers
+            match self.offers.get(&ip_address) {
+                Some((client, _)) => {
+                    if *client_id != *client {
+                        return false;
+                    }
+                }
+                None => {
+                    // Check leases
+                    match self.leases.get(&ip_address) {
+                        Some((client, _, _)) => {
+                            if *client_id != *client {
+                                return false;
+                            }
+                        }
+                        None => {
+                            // Otherwise the address must be free in the pool
+                            if !self.pool.take(ip_address) {
+                                return false;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Renewals are sent directly to us, so they keep the information the
+        // relay agent added to the original request
+        let relay_agent_information = match relay_agent_information {
+            Some(relay_agent_information) => Some(relay_agent_information.clone()),
+            None => match self.leases.get(&ip_address) {
+                Some((_, _, relay_agent_information)) => relay_agent_information.clone(),
+                None => None,
+            },
+        };
+
+        self.offers.remove(&ip_address);
+        self.leases.insert(
+            ip_address,
+            (
+                client_id.clone(),
+                SystemTime::now() + Duration::from_secs(address_time as u64),
+                relay_agent_information,
+            ),
+        );
+        true
+    }
+
+    fn cancel_offer(&mut self, client_id: &ClientID) {
+        let pool = &mut self.pool;
+        self.offers.retain(|ip, (client, _)| {
+            if *client == *client_id {
+                pool.free(*ip);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn get_ip_address(&self, client_id: &ClientID) -> Option<IPAddress> {
+        for (ip, (client, _, _)) in &self.leases {
+            if *client == *client_id {
+                return Some(*ip);
+            }
+        }
+
+        None
+    }
+
+    fn release(&mut self, ip_address: IPAddress, client_id: &ClientID) {
+        if let Some((client, _, _)) = self.leases.get(&ip_address) {
+            if *client == *client_id {
+                self.leases.remove(&ip_address);
+                self.pool.free(ip_address);
+            }
+        }
+    }
+
+    fn decline(&mut self, ip_address: IPAddress, client_id: &ClientID) -> bool {
+        // Verify I.P. is part of the pool
+        if !self.pool.contains(ip_address) {
+            return false;
+        }
+
+        // Only the client holding the address may decline it
+        match self.offers.get(&ip_address) {
+            Some((client, _)) => {
+                if *client_id != *client {
+                    return false;
+                }
+            }
+            None => match self.leases.get(&ip_address) {
+                Some((client, _, _)) => {
+                    if *client_id != *client {
+                        return false;
+                    }
+                }
+                None => return false,
+            },
+        }
+
+        self.offers.remove(&ip_address);
+        self.leases.remove(&ip_address);
+        self.declined.insert(
+            ip_address,
+            (
+                client_id.clone(),
+                SystemTime::now() + Duration::from_secs(self.decline_time),
+            ),
+        );
+        true
+    }
+
+    fn current_leases(&self) -> Vec<(IPAddress, ClientID, Option<RelayAgentInformation>)> {
+        let mut ret = Vec::new();
+
+        for (ip, (client, _, relay_agent_information)) in &self.leases {
+            ret.push((*ip, client.clone(), relay_agent_information.clone()));
+        }
+
+        ret
+    }
+
+    fn current_declined(&self) -> Vec<(IPAddress, ClientID, SystemTime)> {
+        let mut ret = Vec::new();
+
+        for (ip, (client, expiry)) in &self.declined {
+            ret.push((*ip, client.clone(), *expiry));
+        }
+
+        ret
+    }
+}
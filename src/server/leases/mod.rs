@@ -0,0 +1,111 @@
+use crate::{
+    config::{LeaseStoreType, Subnet},
+    dhcp::RelayAgentInformation,
+    ClientID, IPAddress,
+};
+use std::{path::PathBuf, time::SystemTime};
+
+mod file;
+mod memory;
+mod pool;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use file::*;
+pub use memory::*;
+pub use pool::*;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
+pub trait LeaseStore: Send {
+    fn clean_leases(&mut self);
+
+    fn allocate(&mut self, client_id: &ClientID, subnet: &Subnet) -> Option<IPAddress>;
+
+    fn accept_offer(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: &ClientID,
+        relay_agent_information: Option<&RelayAgentInformation>,
+        address_time: u32,
+    ) -> bool;
+
+    fn cancel_offer(&mut self, client_id: &ClientID);
+
+    fn get_ip_address(&self, client_id: &ClientID) -> Option<IPAddress>;
+
+    fn release(&mut self, ip_address: IPAddress, client_id: &ClientID);
+
+    fn decline(&mut self, ip_address: IPAddress, client_id: &ClientID) -> bool;
+
+    fn current_leases(&self) -> Vec<(IPAddress, ClientID, Option<RelayAgentInformation>)>;
+
+    fn current_declined(&self) -> Vec<(IPAddress, ClientID, SystemTime)>;
+}
+
+// An address time of all ones means the lease never expires (RFC 2132
+// section 9.2), these leases last as long as can be stored
+pub const INFINITE_ADDRESS_TIME: u32 = 0xFFFFFFFF;
+
+#[derive(Debug)]
+pub enum LeaseDatabaseError {
+    ReadError(PathBuf, std::io::Error),
+    WriteError(PathBuf, std::io::Error),
+    InvalidRecord(PathBuf, usize),
+    #[cfg(feature = "sqlite")]
+    InvalidRow(PathBuf, &'static str, String),
+    #[cfg(feature = "sqlite")]
+    SQLiteError(PathBuf, rusqlite::Error),
+}
+
+pub fn open(
+    configuration: &crate::config::Configuration,
+) -> Result<Box<dyn LeaseStore>, LeaseDatabaseError> {
+    Ok(match configuration.lease_store() {
+        LeaseStoreType::Memory => Box::new(MemoryLeaseStore::new(configuration)),
+        LeaseStoreType::File => Box::new(FileLeaseStore::open(configuration)?),
+        #[cfg(feature = "sqlite")]
+        LeaseStoreType::SQLite => Box::new(SQLiteLeaseStore::open(configuration)?),
+    })
+}
+
+impl std::error::Error for LeaseDatabaseError {}
+
+impl std::fmt::Display for LeaseDatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LeaseDatabaseError::ReadError(path, error) => format!(
+                    "Unable to read lease database \"{}\" ({})",
+                    path.display(),
+                    error
+                ),
+                LeaseDatabaseError::WriteError(path, error) => format!(
+                    "Unable to write lease database \"{}\" ({})",
+                    path.display(),
+                    error
+                ),
+                LeaseDatabaseError::InvalidRecord(path, line) => format!(
+                    "Invalid record in lease database \"{}\" on line {}",
+                    path.display(),
+                    line
+                ),
+                #[cfg(feature = "sqlite")]
+                LeaseDatabaseError::InvalidRow(path, table, ip) => format!(
+                    "Invalid row for {} in table {} of lease database \"{}\"",
+                    ip,
+                    table,
+                    path.display()
+                ),
+                #[cfg(feature = "sqlite")]
+                LeaseDatabaseError::SQLiteError(path, error) => format!(
+                    "Error in SQLite lease database \"{}\" ({})",
+                    path.display(),
+                    error
+                ),
+            }
+        )
+    }
+}
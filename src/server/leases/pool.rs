@@ -0,0 +1,439 @@
+use crate::IPAddress;
+use std::collections::{BTreeMap, HashMap};
+
+// Tracks which addresses in the lease ranges are free to be handed out.
+//
+// Addresses which have never been handed out are kept as a set of inclusive
+// ranges so that large pools cost nothing until they are used. Addresses that
+// are returned to the pool are queued by subnet in the order they were freed
+// so the least recently used address on a subnet is always handed out first.
+pub struct AddressPool {
+    subnets: BTreeMap<u32, u32>,
+    ranges: BTreeMap<u32, u32>,
+    unused: BTreeMap<u32, u32>,
+    excluded: BTreeMap<u32, u32>,
+    // Keyed by the first address of the subnet
+    released: HashMap<u32, BTreeMap<u64, IPAddress>>,
+    released_order: HashMap<IPAddress, u64>,
+    next_order: u64,
+}
+
+impl AddressPool {
+    pub fn new() -> Self {
+        AddressPool {
+            subnets: BTreeMap::new(),
+            ranges: BTreeMap::new(),
+            unused: BTreeMap::new(),
+            excluded: BTreeMap::new(),
+            released: HashMap::new(),
+            released_order: HashMap::new(),
+            next_order: 0,
+        }
+    }
+
+    // Subnets must not overlap, only addresses inside a subnet are handed out
+    pub fn add_subnet(&mut self, first: IPAddress, last: IPAddress) {
+        self.subnets.insert(first.to_u32(), last.to_u32());
+    }
+
+    // Ranges must be added before any addresses are excluded or handed out
+    pub fn add_range(&mut self, start: IPAddress, end: IPAddress) {
+        let mut start = start.to_u32();
+        let mut end = end.to_u32();
+        if start > end {
+            return;
+        }
+
+        for (range_start, range_end) in overlapping_ranges(&self.ranges, start, end) {
+            self.ranges.remove(&range_start);
+            start = start.min(range_start);
+            end = end.max(range_end);
+        }
+        self.ranges.insert(start, end);
+
+        self.unused.clear();
+        let ranges: Vec<(u32, u32)> = self
+            .ranges
+            .iter()
+            .map(|(start, end)| (*start, *end))
+            .collect();
+        for (start, end) in ranges {
+            self.insert_unused(start, end);
+        }
+    }
+
+    pub fn contains(&self, ip_address: IPAddress) -> bool {
+        range_contains(&self.ranges, ip_address.to_u32())
+            && range_contains(&self.subnets, ip_address.to_u32())
+            && is_usable(ip_address)
+            && !range_contains(&self.excluded, ip_address.to_u32())
+    }
+
+    // Permanently removes a range of addresses from the pool
+    pub fn exclude(&mut self, start: IPAddress, end: IPAddress) {
+        let mut start = start.to_u32();
+        let mut end = end.to_u32();
+        if start > end {
+            return;
+        }
+
+        // Remove the range from the unused addresses
+        for (range_start, range_end) in overlapping_ranges(&self.unused, start, end) {
+            self.unused.remove(&range_start);
+            if range_start < start {
+                self.insert_unused(range_start, start - 1);
+            }
+            if range_end > end {
+                self.insert_unused(end + 1, range_end);
+            }
+        }
+
+        // Remove any released addresses in the range
+        let released: Vec<IPAddress> = self
+            .released_order
+            .keys()
+            .filter(|ip_address| ip_address.to_u32() >= start && ip_address.to_u32() <= end)
+            .copied()
+            .collect();
+        for ip_address in released {
+            self.remove_released(ip_address);
+        }
+
+        // Merge with any excluded ranges this overlaps
+        for (range_start, range_end) in overlapping_ranges(&self.excluded, start, end) {
+            self.excluded.remove(&range_start);
+            start = start.min(range_start);
+            end = end.max(range_end);
+        }
+        self.excluded.insert(start, end);
+    }
+
+    // Takes the least recently used free address of the subnet from "first"
+    // to "last" out of the pool, the subnet must have been added
+    pub fn allocate(&mut self, first: IPAddress, last: IPAddress) -> Option<IPAddress> {
+        let first = first.to_u32();
+        let last = last.to_u32();
+
+        // Addresses that have never been used come first
+        let mut next = first;
+        while next <= last {
+            let (start, end) = match first_overlapping_range(&self.unused, next, last) {
+                Some(range) => range,
+                None => break,
+            };
+
+            let end = end.min(last);
+            if let Some(address) = first_usable(start.max(next), end) {
+                self.take_unused(address);
+                return Some(IPAddress::from_u32(address));
+            }
+
+            next = match end.checked_add(1) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        let (_, ip_address) = match self.released.get_mut(&first) {
+            Some(released) => released.pop_first()?,
+            None => return None,
+        };
+
+        self.released_order.remove(&ip_address);
+        Some(ip_address)
+    }
+
+    // Takes a specific address out of the pool, returns false if it is not free
+    pub fn take(&mut self, ip_address: IPAddress) -> bool {
+        if !self.contains(ip_address) {
+            return false;
+        }
+
+        if self.remove_released(ip_address) {
+            return true;
+        }
+
+        self.take_unused(ip_address.to_u32())
+    }
+
+    // Returns an address to the pool as the most recently used address
+    pub fn free(&mut self, ip_address: IPAddress) {
+        if !self.contains(ip_address) || self.is_free(ip_address) {
+            return;
+        }
+
+        let subnet = match self.subnet(ip_address) {
+            Some(subnet) => subnet,
+            None => return,
+        };
+
+        let order = self.next_order;
+        self.next_order += 1;
+
+        self.released
+            .entry(subnet)
+            .or_default()
+            .insert(order, ip_address);
+        self.released_order.insert(ip_address, order);
+    }
+
+    pub fn is_free(&self, ip_address: IPAddress) -> bool {
+        if self.released_order.contains_key(&ip_address) {
+            return true;
+        }
+
+        range_contains(&self.unused, ip_address.to_u32())
+    }
+
+    // The first address of the subnet holding an address
+    fn subnet(&self, ip_address: IPAddress) -> Option<u32> {
+        let address = ip_address.to_u32();
+        match self.subnets.range(..=address).next_back() {
+            Some((first, last)) => {
+                if address <= *last {
+                    Some(*first)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+
+    fn remove_released(&mut self, ip_address: IPAddress) -> bool {
+        let order = match self.released_order.remove(&ip_address) {
+            Some(order) => order,
+            None => return false,
+        };
+
+        if let Some(subnet) = self.subnet(ip_address) {
+            if let Some(released) = self.released.get_mut(&subnet) {
+                released.remove(&order);
+            }
+        }
+
+        true
+    }
+
+    // Splits an address out of the range of unused addresses holding it
+    fn take_unused(&mut self, address: u32) -> bool {
+        let (start, end) = match self.unused.range(..=address).next_back() {
+            Some((start, end)) => (*start, *end),
+            None => return false,
+        };
+
+        if address > end {
+            return false;
+        }
+
+        self.unused.remove(&start);
+        if start < address {
+            self.insert_unused(start, address - 1);
+        }
+        if address < end {
+            self.insert_unused(address + 1, end);
+        }
+
+        true
+    }
+
+    // Ranges left holding only network and broadcast addresses are dropped so
+    // allocation never has to walk over them
+    fn insert_unused(&mut self, start: u32, end: u32) {
+        if first_usable(start, end).is_some() {
+            self.unused.insert(start, end);
+        }
+    }
+}
+
+fn range_contains(ranges: &BTreeMap<u32, u32>, address: u32) -> bool {
+    match ranges.range(..=address).next_back() {
+        Some((_, end)) => address <= *end,
+        None => false,
+    }
+}
+
+// Finds the ranges in a set of non-overlapping ranges which overlap start..=end
+fn overlapping_ranges(ranges: &BTreeMap<u32, u32>, start: u32, end: u32) -> Vec<(u32, u32)> {
+    let mut overlapping = Vec::new();
+
+    if let Some((range_start, range_end)) = ranges.range(..start).next_back() {
+        if *range_end >= start {
+            overlapping.push((*range_start, *range_end));
+        }
+    }
+
+    for (range_start, range_end) in ranges.range(start..=end) {
+        overlapping.push((*range_start, *range_end));
+    }
+
+    overlapping
+}
+
+// Finds the first range in a set of non-overlapping ranges which overlaps
+// start..=end
+fn first_overlapping_range(
+    ranges: &BTreeMap<u32, u32>,
+    start: u32,
+    end: u32,
+) -> Option<(u32, u32)> {
+    if let Some((range_start, range_end)) = ranges.range(..start).next_back() {
+        if *range_end >= start {
+            return Some((*range_start, *range_end));
+        }
+    }
+
+    ranges
+        .range(start..=end)
+        .next()
+        .map(|(range_start, range_end)| (*range_start, *range_end))
+}
+
+// At most two addresses in a row are unusable
+fn first_usable(start: u32, end: u32) -> Option<u32> {
+    let mut address = start;
+    loop {
+        if is_usable(IPAddress::from_u32(address)) {
+            return Some(address);
+        }
+
+        if address >= end {
+            return None;
+        }
+        address += 1;
+    }
+}
+
+// Addresses ending in 0 or 255 are never handed out as they may be network or
+// broadcast addresses and some programs don't like them
+fn is_usable(ip_address: IPAddress) -> bool {
+    let last = ip_address.as_slice()[3];
+    last != 0 && last != 255
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: [u8; 4]) -> IPAddress {
+        IPAddress::new(address)
+    }
+
+    fn pool(subnets: &[([u8; 4], [u8; 4])], ranges: &[([u8; 4], [u8; 4])]) -> AddressPool {
+        let mut pool = AddressPool::new();
+        for (first, last) in subnets {
+            pool.add_subnet(ip(*first), ip(*last));
+        }
+        for (start, end) in ranges {
+            pool.add_range(ip(*start), ip(*end));
+        }
+        pool
+    }
+
+    #[test]
+    fn allocate_skips_unusable_addresses() {
+        let mut pool = pool(
+            &[([10, 0, 0, 0], [10, 0, 1, 255])],
+            &[([10, 0, 0, 254], [10, 0, 1, 2])],
+        );
+
+        let first = ip([10, 0, 0, 0]);
+        let last = ip([10, 0, 1, 255]);
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 0, 254])));
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 1, 1])));
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 1, 2])));
+        assert_eq!(pool.allocate(first, last), None);
+    }
+
+    #[test]
+    fn exhausted_ranges_leave_no_unusable_fragments() {
+        let mut pool = pool(
+            &[([10, 0, 0, 0], [10, 0, 3, 255])],
+            &[([10, 0, 0, 0], [10, 0, 3, 255])],
+        );
+
+        let first = ip([10, 0, 0, 0]);
+        let last = ip([10, 0, 3, 255]);
+        for _ in 0..4 * 254 {
+            assert!(pool.allocate(first, last).is_some());
+            for (start, end) in &pool.unused {
+                assert!(first_usable(*start, *end).is_some());
+            }
+        }
+
+        assert!(pool.unused.is_empty());
+        assert_eq!(pool.allocate(first, last), None);
+    }
+
+    #[test]
+    fn allocate_reuses_least_recently_freed_address() {
+        let mut pool = pool(
+            &[([10, 0, 0, 0], [10, 0, 0, 255])],
+            &[([10, 0, 0, 1], [10, 0, 0, 3])],
+        );
+
+        let first = ip([10, 0, 0, 0]);
+        let last = ip([10, 0, 0, 255]);
+        for _ in 0..3 {
+            assert!(pool.allocate(first, last).is_some());
+        }
+
+        pool.free(ip([10, 0, 0, 2]));
+        pool.free(ip([10, 0, 0, 1]));
+        assert!(pool.is_free(ip([10, 0, 0, 2])));
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 0, 2])));
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 0, 1])));
+        assert_eq!(pool.allocate(first, last), None);
+    }
+
+    #[test]
+    fn allocate_stays_in_subnet() {
+        let mut pool = pool(
+            &[
+                ([10, 0, 0, 0], [10, 0, 0, 255]),
+                ([10, 0, 1, 0], [10, 0, 1, 255]),
+            ],
+            &[
+                ([10, 0, 0, 1], [10, 0, 0, 1]),
+                ([10, 0, 1, 1], [10, 0, 1, 1]),
+            ],
+        );
+
+        let second = (ip([10, 0, 1, 0]), ip([10, 0, 1, 255]));
+        assert!(pool.take(ip([10, 0, 0, 1])));
+        assert_eq!(pool.allocate(second.0, second.1), Some(ip([10, 0, 1, 1])));
+
+        // An address freed on the first subnet isn't handed out on the second
+        pool.free(ip([10, 0, 0, 1]));
+        assert_eq!(pool.allocate(second.0, second.1), None);
+        assert_eq!(
+            pool.allocate(ip([10, 0, 0, 0]), ip([10, 0, 0, 255])),
+            Some(ip([10, 0, 0, 1]))
+        );
+    }
+
+    #[test]
+    fn excluded_and_taken_addresses_are_not_allocated() {
+        let mut pool = pool(
+            &[([10, 0, 0, 0], [10, 0, 0, 255])],
+            &[([10, 0, 0, 1], [10, 0, 0, 4])],
+        );
+
+        pool.exclude(ip([10, 0, 0, 1]), ip([10, 0, 0, 2]));
+        assert!(!pool.contains(ip([10, 0, 0, 1])));
+        assert!(!pool.take(ip([10, 0, 0, 2])));
+        assert!(pool.take(ip([10, 0, 0, 4])));
+        assert!(!pool.take(ip([10, 0, 0, 4])));
+
+        let first = ip([10, 0, 0, 0]);
+        let last = ip([10, 0, 0, 255]);
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 0, 3])));
+        assert_eq!(pool.allocate(first, last), None);
+
+        // Freeing an address twice only queues it once
+        pool.free(ip([10, 0, 0, 3]));
+        pool.free(ip([10, 0, 0, 3]));
+        assert_eq!(pool.allocate(first, last), Some(ip([10, 0, 0, 3])));
+        assert_eq!(pool.allocate(first, last), None);
+    }
+}
@@ -0,0 +1,231 @@
+use super::{LeaseDatabaseError, LeaseStore, MemoryLeaseStore};
+use crate::{config::Subnet, dhcp::RelayAgentInformation, ClientID, IPAddress};
+use rusqlite::{params, Connection};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Leases are kept in memory and written through to a SQLite database. The
+// client column holds the client identifier, written the same way as in the
+// lease file.
+pub struct SQLiteLeaseStore {
+    memory: MemoryLeaseStore,
+    path: PathBuf,
+    connection: Connection,
+}
+
+// The ip, client, expiry and relay agent information columns of a row
+type Record = (String, String, i64, Option<Vec<u8>>);
+
+impl SQLiteLeaseStore {
+    pub fn open(configuration: &crate::config::Configuration) -> Result<Self, LeaseDatabaseError> {
+        let logger = logging::get_logger(module_path!());
+
+        let path = configuration.lease_file().to_owned();
+        let mut memory = MemoryLeaseStore::new(configuration);
+
+        let connection = match Connection::open(&path) {
+            Ok(connection) => connection,
+            Err(error) => return Err(LeaseDatabaseError::SQLiteError(path, error)),
+        };
+
+        match connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS leases (
+                ip TEXT PRIMARY KEY,
+                client TEXT NOT NULL,
+                expiry INTEGER NOT NULL,
+                relay_agent_information BLOB
+            );
+            CREATE TABLE IF NOT EXISTS declined (
+                ip TEXT PRIMARY KEY,
+                client TEXT NOT NULL,
+                expiry INTEGER NOT NULL
+            );",
+        ) {
+            Ok(()) => {}
+            Err(error) => return Err(LeaseDatabaseError::SQLiteError(path, error)),
+        }
+
+        // Load the stored leases and quarantined addresses
+        for table in ["leases", "declined"] {
+            let records = match load_table(&connection, table) {
+                Ok(records) => records,
+                Err(error) => return Err(LeaseDatabaseError::SQLiteError(path, error)),
+            };
+
+            for (key, client, expiry, relay_agent_information) in records {
+                let ip = match IPAddress::parse(&key) {
+                    Some(ip) => ip,
+                    None => return Err(LeaseDatabaseError::InvalidRow(path, table, key)),
+                };
+
+                let client = match ClientID::parse(&client) {
+                    Some(client) => client,
+                    None => return Err(LeaseDatabaseError::InvalidRow(path, table, key)),
+                };
+
+                let relay_agent_information = match relay_agent_information {
+                    Some(value) => match RelayAgentInformation::parse(&value) {
+                        Ok(relay_agent_information) => Some(relay_agent_information),
+                        Err(_) => return Err(LeaseDatabaseError::InvalidRow(path, table, key)),
+                    },
+                    None => None,
+                };
+
+                let expiry = UNIX_EPOCH + Duration::from_secs(expiry as u64);
+                if table == "leases" {
+                    memory.restore_lease(ip, client, expiry, relay_agent_information);
+                } else {
+                    memory.restore_declined(ip, client, expiry);
+                }
+            }
+        }
+
+        let mut store = SQLiteLeaseStore {
+            memory,
+            path,
+            connection,
+        };
+        store.clean_leases();
+
+        logging::info!(
+            logger,
+            "Loaded {} leases from {}",
+            store.memory.leases().count(),
+            store.path.display()
+        );
+
+        Ok(store)
+    }
+
+    fn execute<P: rusqlite::Params>(&self, sql: &str, params: P) {
+        let logger = logging::get_logger(module_path!());
+
+        match self.connection.execute(sql, params) {
+            Ok(_) => {}
+            Err(error) => logging::error!(
+                logger,
+                "Unable to write to lease database {} ({})",
+                self.path.display(),
+                error
+            ),
+        }
+    }
+}
+
+impl LeaseStore for SQLiteLeaseStore {
+    fn clean_leases(&mut self) {
+        self.memory.clean_leases();
+        let now = unix_time(SystemTime::now());
+        self.execute("DELETE FROM leases WHERE expiry <= ?1", params![now]);
+        self.execute("DELETE FROM declined WHERE expiry <= ?1", params![now]);
+    }
+
+    fn allocate(&mut self, client_id: &ClientID, subnet: &Subnet) -> Option<IPAddress> {
+        self.memory.allocate(client_id, subnet)
+    }
+
+    fn accept_offer(
+        &mut self,
+        ip_address: IPAddress,
+        client_id: &ClientID,
+        relay_agent_information: Option<&RelayAgentInformation>,
+        address_time: u32,
+    ) -> bool {
+        if !self
+            .memory
+            .accept_offer(ip_address, client_id, relay_agent_information, address_time)
+        {
+            return false;
+        }
+
+        if let Some((client, expiry, relay_agent_information)) = self.memory.lease(ip_address) {
+            self.execute(
+                "INSERT OR REPLACE INTO leases (ip, client, expiry, relay_agent_information) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    ip_address.to_string(),
+                    client.to_string(),
+                    unix_time(expiry),
+                    relay_agent_information
+                        .map(|relay_agent_information| relay_agent_information.generate())
+                ],
+            );
+        }
+
+        true
+    }
+
+    fn cancel_offer(&mut self, client_id: &ClientID) {
+        self.memory.cancel_offer(client_id)
+    }
+
+    fn get_ip_address(&self, client_id: &ClientID) -> Option<IPAddress> {
+        self.memory.get_ip_address(client_id)
+    }
+
+    fn release(&mut self, ip_address: IPAddress, client_id: &ClientID) {
+        self.memory.release(ip_address, client_id);
+        self.execute(
+            "DELETE FROM leases WHERE ip = ?1 AND client = ?2",
+            params![ip_address.to_string(), client_id.to_string()],
+        );
+    }
+
+    fn decline(&mut self, ip_address: IPAddress, client_id: &ClientID) -> bool {
+        if !self.memory.decline(ip_address, client_id) {
+            return false;
+        }
+
+        if let Some((client, expiry)) = self.memory.declined(ip_address) {
+            self.execute(
+                "DELETE FROM leases WHERE ip = ?1",
+                params![ip_address.to_string()],
+            );
+            self.execute(
+                "INSERT OR REPLACE INTO declined (ip, client, expiry) VALUES (?1, ?2, ?3)",
+                params![
+                    ip_address.to_string(),
+                    client.to_string(),
+                    unix_time(expiry)
+                ],
+            );
+        }
+
+        true
+    }
+
+    fn current_leases(&self) -> Vec<(IPAddress, ClientID, Option<RelayAgentInformation>)> {
+        self.memory.current_leases()
+    }
+
+    fn current_declined(&self) -> Vec<(IPAddress, ClientID, SystemTime)> {
+        self.memory.current_declined()
+    }
+}
+
+// Only leases record relay agent information
+fn load_table(connection: &Connection, table: &str) -> rusqlite::Result<Vec<Record>> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT ip, client, expiry, {} FROM {}",
+        if table == "leases" {
+            "relay_agent_information"
+        } else {
+            "NULL"
+        },
+        table
+    ))?;
+    let records = statement
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect();
+    records
+}
+
+fn unix_time(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(_) => 0,
+    }
+}
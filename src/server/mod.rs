@@ -1,34 +1,58 @@
-use self::leases::Leases;
+use self::leases::{LeaseStore, INFINITE_ADDRESS_TIME};
 use crate::{
-    dhcp::{DHCPOptionClass, DHCPPacket, HardwareType, MessageType},
-    u32_to_slice, IPAddress, MACAddress,
+    config::{Reservation, Subnet},
+    dhcp::{
+        DHCPOptionClass, DHCPPacket, DHCPPacketView, HardwareAddress, HardwareType, MessageType,
+        OptionValue, OptionValueError, ParseOptionError, RelayAgentInformation, ReplyBuilder,
+        MAX_HARDWARE_ADDRESS_LENGTH,
+    },
+    ClientID, IPAddress,
 };
-use std::{collections::HashMap, net::SocketAddr};
+use std::{net::SocketAddr, time::SystemTime};
 
 mod leases;
 
+pub use leases::LeaseDatabaseError;
+
 pub struct DHCPServer {
-    leases: Leases,
-    reserved: HashMap<MACAddress, IPAddress>,
+    leases: Box<dyn LeaseStore>,
+    reservations: Vec<Reservation>,
     our_ip: IPAddress,
-    gateway_ip: IPAddress,
-    subnet_mask: IPAddress,
-    broadcast_address: IPAddress,
-    dns: IPAddress,
-    dns_alternative: IPAddress,
+    // Each subnet with the options sent to clients on it
+    subnets: Vec<(Subnet, Vec<(DHCPOptionClass, OptionValue)>)>,
+    address_time: u32,
+    renewal_time: u32,
+    rebinding_time: u32,
+    boot_server: IPAddress,
+    boot_file: Option<String>,
+    dynamic_bootp: bool,
+    trusted_relays: Vec<IPAddress>,
+}
+
+// Who a request came from
+struct Client {
+    id: ClientID,
+    hardware_address: HardwareAddress,
+    relay_agent_information: Option<RelayAgentInformation>,
+    // The subnets on the client's link, several if it is a shared network
+    subnets: Vec<usize>,
+    // Our address on the interface the request arrived on
+    server_id: IPAddress,
+    // The subnet was chosen by the subnet selection option
+    subnet_selection: bool,
 }
 
 #[derive(Debug)]
 pub enum HandlePacketError {
-    MalformedOption,
+    MalformedOption(DHCPOptionClass, OptionValueError),
+    MalformedRelayAgentInformation(ParseOptionError),
     NoMsgType,
-    InvalidHardwareType(HardwareType),
-    InvalidHardwareAddressLength(u8),
+    InvalidHardwareAddressLength(HardwareType, u8),
+    NoClientID(HardwareType),
     NoIPAddressesAvailable,
-    InvalidRequestedAddressLength,
     NoRequestedIPInRequest,
-    InvalidRenewAddress,
-    DeclineMessageRecieved,
+    NoServerIDInDecline,
+    NoSubnet(IPAddress),
 }
 
 pub const DHCP_SERVER_PORT: u16 = 67;
@@ -44,33 +68,94 @@ const DHCP_MESSAGE_TYPE_RELEASE: u8 = 7;
 const DHCP_MESSAGE_TYPE_INFORM: u8 = 8;
 
 impl DHCPServer {
-    pub fn new(configuration: &crate::config::Configuration) -> Self {
-        let mut reserved = HashMap::new();
-        for (mac, ip) in configuration.reserved_ips() {
-            reserved.insert(*mac, *ip);
+    pub fn new(configuration: &crate::config::Configuration) -> Result<Self, LeaseDatabaseError> {
+        let logger = logging::get_logger(module_path!());
+
+        for reservation in configuration.reservations() {
+            let ip = reservation.ip_address();
+            if configuration.subnets().iter().any(|subnet| {
+                subnet
+                    .pools()
+                    .iter()
+                    .any(|(start, end)| ip >= *start && ip <= *end)
+            }) {
+                logging::warning!(
+                    logger,
+                    "Reserved address {} for {} is inside the lease range, it will not be leased to other clients",
+                    ip,
+                    reservation
+                );
+            }
         }
 
+        // Options which are sent to clients that request them
         let (dns, dns_alternative) = configuration.dns();
 
-        DHCPServer {
-            leases: Leases::new(&configuration),
-            reserved,
-            our_ip: configuration.our_ip(),
-            gateway_ip: configuration.gateway_ip(),
-            subnet_mask: configuration.subnet_mask(),
-            broadcast_address: configuration.broadcast_address(),
-            dns,
-            dns_alternative,
+        let mut subnets = Vec::new();
+        for subnet in configuration.subnets() {
+            let mut options = vec![
+                (
+                    DHCPOptionClass::SubnetMask,
+                    OptionValue::IPAddress(vec![subnet.subnet_mask()]),
+                ),
+                (
+                    DHCPOptionClass::BroadcastAddress,
+                    OptionValue::IPAddress(vec![subnet.broadcast_address()]),
+                ),
+            ];
+            if let Some(gateway_ip) = subnet.gateway_ip() {
+                options.push((
+                    DHCPOptionClass::Gateways,
+                    OptionValue::IPAddress(vec![gateway_ip]),
+                ));
+            }
+            options.push((
+                DHCPOptionClass::DomainServer,
+                OptionValue::IPAddress(vec![dns, dns_alternative]),
+            ));
+
+            // Configured options replace the built in ones, the subnet's own
+            // options replace the global ones
+            for (option_class, value) in configuration.options().iter().chain(subnet.options()) {
+                options.retain(|(class, _)| class != option_class);
+                options.push((*option_class, value.clone()));
+            }
+
+            subnets.push((subnet.clone(), options));
         }
+
+        Ok(DHCPServer {
+            leases: leases::open(configuration)?,
+            reservations: configuration.reservations().clone(),
+            our_ip: configuration.our_ip(),
+            subnets,
+            address_time: configuration.address_time(),
+            renewal_time: configuration.renewal_time(),
+            rebinding_time: configuration.rebinding_time(),
+            boot_server: configuration.boot_server(),
+            boot_file: configuration
+                .boot_file()
+                .map(|boot_file| boot_file.to_owned()),
+            dynamic_bootp: configuration.dynamic_bootp(),
+            trusted_relays: configuration.trusted_relays().clone(),
+        })
     }
 
-    pub fn current_leases(&self) -> Vec<(IPAddress, MACAddress)> {
+    pub fn current_leases(&self) -> Vec<(IPAddress, ClientID, Option<RelayAgentInformation>)> {
         self.leases.current_leases()
     }
 
+    pub fn current_declined(&self) -> Vec<(IPAddress, ClientID, SystemTime)> {
+        self.leases.current_declined()
+    }
+
+    // The local address is the address the request arrived at, if known.
+    // It is used as the server identifier so clients on every interface of a
+    // multi-homed server can reach us.
     pub fn handle_packet(
         &mut self,
-        packet: DHCPPacket,
+        packet: &DHCPPacketView,
+        local_address: Option<IPAddress>,
     ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
         let logger = logging::get_logger(module_path!());
 
@@ -83,41 +168,126 @@ impl DHCPServer {
         self.leases.clean_leases();
 
         // Get packet type
-        let packet_type = match packet.get_option(DHCPOptionClass::DHCPMsgType) {
-            Some(value) => match value.get(0) {
-                Some(packet_type) => *packet_type,
-                None => return Err(HandlePacketError::MalformedOption),
+        // Requests without a message type are from BOOTP clients
+        let packet_type = match get_value(packet, DHCPOptionClass::DHCPMsgType)? {
+            Some(value) => match value.as_u8() {
+                Some(packet_type) => Some(packet_type),
+                None => return Err(HandlePacketError::NoMsgType),
             },
-            None => return Err(HandlePacketError::NoMsgType),
+            None => None,
         };
 
-        // Parse MACAddress
-        let mac_address = match packet.hardware_type() {
-            HardwareType::Ethernet => match packet.hardware_address_length() {
-                6 => {
-                    let address = packet.client_hardware_address();
-                    MACAddress::new([
-                        address[0], address[1], address[2], address[3], address[4], address[5],
-                    ])
-                }
+        // Ethernet addresses are always 6 bytes, other types may use any
+        // length that fits in chaddr
+        let hardware_address_length = packet.hardware_address_length();
+        match packet.hardware_type() {
+            HardwareType::Ethernet => match hardware_address_length {
+                6 => {}
                 _ => {
                     return Err(HandlePacketError::InvalidHardwareAddressLength(
-                        packet.hardware_address_length(),
+                        packet.hardware_type(),
+                        hardware_address_length,
                     ))
                 }
             },
             _ => {
-                return Err(HandlePacketError::InvalidHardwareType(
-                    packet.hardware_type(),
-                ))
+                if hardware_address_length as usize > MAX_HARDWARE_ADDRESS_LENGTH {
+                    return Err(HandlePacketError::InvalidHardwareAddressLength(
+                        packet.hardware_type(),
+                        hardware_address_length,
+                    ));
+                }
+            }
+        }
+        let hardware_address = packet.client_hardware_address();
+
+        // Relay agents may describe where the client is connected. Only
+        // relayed requests may carry it (RFC 3046 section 2.1), otherwise a
+        // client could claim to be on any port, and only from trusted relays
+        // if any are configured.
+        let relay_agent_information = if self.is_relay(packet.gateway_ip_address()) {
+            match get_value(packet, DHCPOptionClass::RelayAgentInformation)? {
+                Some(value) => match value.as_binary() {
+                    Some(value) => match RelayAgentInformation::parse(value) {
+                        Ok(relay_agent_information) => Some(relay_agent_information),
+                        Err(error) => {
+                            return Err(HandlePacketError::MalformedRelayAgentInformation(error))
+                        }
+                    },
+                    None => None,
+                },
+                None => None,
             }
+        } else {
+            None
+        };
+
+        // Bindings are keyed on the client identifier, clients which don't
+        // send one are identified by their hardware address
+        let client_id = match get_value(packet, DHCPOptionClass::ClientID)? {
+            Some(value) => value
+                .as_binary()
+                .map(|client_id| ClientID::new(client_id.to_vec())),
+            None => None,
+        };
+        let client_id = match client_id {
+            Some(client_id) => client_id,
+            None => match hardware_address.hardware_type() {
+                // InfiniBand addresses don't fit in chaddr so clients must
+                // send a client identifier (RFC 4390 section 2.1)
+                HardwareType::InfiniBand => {
+                    return Err(HandlePacketError::NoClientID(
+                        hardware_address.hardware_type(),
+                    ))
+                }
+                _ => ClientID::from_hardware_address(
+                    hardware_address.hardware_type().generate(),
+                    hardware_address.as_slice(),
+                ),
+            },
+        };
+
+        let server_id = match local_address {
+            Some(local_address) => local_address,
+            None => self.our_ip,
+        };
+
+        let (subnets, subnet_selection) =
+            self.select_subnets(packet, relay_agent_information.as_ref(), server_id)?;
+        let client = Client {
+            id: client_id,
+            hardware_address,
+            relay_agent_information,
+            subnets,
+            server_id,
+            subnet_selection,
+        };
+
+        if let Some(relay_agent_information) = &client.relay_agent_information {
+            logging::info!(
+                logger,
+                "{} relayed by {} ({})",
+                client,
+                packet.gateway_ip_address(),
+                relay_agent_information
+            );
+        }
+
+        // Replies must fit in the largest message the client accepts
+        let max_message_size = match get_value(packet, DHCPOptionClass::DHCPMaxMsgSize)? {
+            Some(value) => value.as_u16(),
+            None => None,
         };
 
         // Parse packet type
-        match packet_type {
-            DHCP_MESSAGE_TYPE_DISCOVER => {
-                logging::info!(logger, "Recieved DISCOVER packet from {}", mac_address);
-                self.handle_discover_packet(&packet, mac_address)
+        let response = match packet_type {
+            None => {
+                logging::info!(logger, "Recieved BOOTREQUEST packet from {}", client);
+                self.handle_bootp_packet(packet, &client)
+            }
+            Some(DHCP_MESSAGE_TYPE_DISCOVER) => {
+                logging::info!(logger, "Recieved DISCOVER packet from {}", client);
+                self.handle_discover_packet(packet, &client)
                     .map(move |response_packet| {
                         Some((
                             response_packet,
@@ -135,35 +305,43 @@ impl DHCPServer {
                         ))
                     })
             }
-            DHCP_MESSAGE_TYPE_REQUEST => {
-                logging::info!(logger, "Recieved REQUEST packet from {}", mac_address);
-                self.handle_request_packet(packet, mac_address)
-                    .map(|response| Some(response))
+            Some(DHCP_MESSAGE_TYPE_REQUEST) => {
+                logging::info!(logger, "Recieved REQUEST packet from {}", client);
+                self.handle_request_packet(packet, &client)
             }
-            DHCP_MESSAGE_TYPE_DECLINE => Err(HandlePacketError::DeclineMessageRecieved),
-            DHCP_MESSAGE_TYPE_RELEASE => {
-                logging::info!(logger, "Recieved RELEASE packet from {}", mac_address);
-                logging::info!(
-                    logger,
-                    "{} released {}",
-                    mac_address,
-                    packet.client_ip_address()
-                );
-                self.leases.release(packet.client_ip_address(), mac_address);
+            Some(DHCP_MESSAGE_TYPE_DECLINE) => {
+                logging::info!(logger, "Recieved DECLINE packet from {}", client);
+                self.handle_decline_packet(packet, &client).map(|()| None)
+            }
+            Some(DHCP_MESSAGE_TYPE_RELEASE) => {
+                logging::info!(logger, "Recieved RELEASE packet from {}", client);
+                logging::info!(logger, "{} released {}", client, packet.client_ip_address());
+                self.leases.release(packet.client_ip_address(), &client.id);
                 Ok(None)
             }
-            DHCP_MESSAGE_TYPE_INFORM => {
-                logging::info!(logger, "Recieved INFORM packet from {}", mac_address);
-                Ok(Some(self.generate_ack_packet(packet, None, mac_address)))
+            Some(DHCP_MESSAGE_TYPE_INFORM) => {
+                logging::info!(logger, "Recieved INFORM packet from {}", client);
+                Ok(Some(self.generate_ack_packet(packet, &client, None)))
             }
             _ => Ok(None),
-        }
+        }?;
+
+        Ok(match response {
+            Some((mut response_packet, target)) => {
+                if let Some(max_message_size) = max_message_size {
+                    response_packet.set_max_message_size(max_message_size as usize);
+                }
+
+                Some((response_packet, target))
+            }
+            None => None,
+        })
     }
 
     fn handle_discover_packet(
         &mut self,
-        packet: &DHCPPacket,
-        mac_address: MACAddress,
+        packet: &DHCPPacketView,
+        client: &Client,
     ) -> Result<DHCPPacket, HandlePacketError> {
         let logger = logging::get_logger(module_path!());
 
@@ -173,21 +351,18 @@ impl DHCPServer {
         if packet.client_ip_address() != IPAddress::new([0, 0, 0, 0]) {
             // See if the client ip is available
             // First in reserved
-            match self.reserved.get(&mac_address) {
+            match self.reserved_ip(client) {
                 Some(ip_address) => {
-                    if *ip_address == packet.client_ip_address() {
-                        return_ip = Some(*ip_address);
+                    if ip_address == packet.client_ip_address() {
+                        return_ip = Some(ip_address);
                     }
                 }
                 None => {
                     // Secondly in leases
-                    match self.leases.get_ip_address(mac_address) {
-                        Some(ip_address) => {
-                            if ip_address == packet.client_ip_address() {
-                                return_ip = Some(ip_address);
-                            }
+                    if let Some(ip_address) = self.leases.get_ip_address(&client.id) {
+                        if ip_address == packet.client_ip_address() {
+                            return_ip = Some(ip_address);
                         }
-                        None => {}
                     }
                 }
             }
@@ -196,26 +371,13 @@ impl DHCPServer {
         let return_ip = match return_ip {
             Some(return_ip) => return_ip,
             None => {
-                // Check to see if there is a reserved address
-                let mut reserved_ip = None;
-                for (reserved_mac_address, ip_address) in &self.reserved {
-                    if *reserved_mac_address == mac_address {
-                        reserved_ip = Some(*ip_address);
-                        break;
-                    }
-                }
-
-                // Otherwise, allocate from lease
-                match reserved_ip {
+                // Check to see if there is a reserved address, otherwise
+                // allocate from lease
+                match self.reserved_ip(client) {
                     Some(ip) => ip,
-                    None => match self.leases.allocate(mac_address) {
+                    None => match self.allocate(client) {
                         Some(ip) => {
-                            logging::info!(
-                                logger,
-                                "Creating lease offer for {} to {}",
-                                ip,
-                                mac_address
-                            );
+                            logging::info!(logger, "Creating lease offer for {} to {}", ip, client);
                             ip
                         }
                         None => return Err(HandlePacketError::NoIPAddressesAvailable),
@@ -225,201 +387,536 @@ impl DHCPServer {
         };
 
         // Send offer
-        logging::info!(logger, "Offering {} to {}", return_ip, mac_address);
-
-        let mut packet = DHCPPacket::new(
-            packet.transaction_id(),
-            packet.flags(),
-            IPAddress::new([0, 0, 0, 0]),
-            return_ip,
-            self.our_ip,
-            packet.gateway_ip_address(),
-            *packet.client_hardware_address(),
-        );
+        logging::info!(logger, "Offering {} to {}", return_ip, client);
 
-        packet.add_option(DHCPOptionClass::DHCPMsgType, &[DHCP_MESSAGE_TYPE_OFFER]);
-        packet.add_option(DHCPOptionClass::DHCPServerID, self.our_ip.as_slice());
-        packet.add_option(
-            DHCPOptionClass::AddressTime,
-            &u32_to_slice(self.leases.address_time()),
-        );
-        packet.add_option(
-            DHCPOptionClass::RenewalTime,
-            &u32_to_slice(self.leases.renewal_time()),
-        );
-        packet.add_option(
-            DHCPOptionClass::RebindingTime,
-            &u32_to_slice(self.leases.rebinding_time()),
-        ); // 1 Day, 18 Hours
-        packet.add_option(DHCPOptionClass::SubnetMask, self.subnet_mask.as_slice());
-        packet.add_option(
-            DHCPOptionClass::BroadcastAddress,
-            self.broadcast_address.as_slice(),
-        );
-        packet.add_option(DHCPOptionClass::Gateways, self.gateway_ip.as_slice());
-        let mut dns = Vec::from(self.dns.as_slice());
-        dns.extend_from_slice(self.dns_alternative.as_slice());
-        packet.add_option(DHCPOptionClass::DomainServer, dns.as_slice());
-        let mut client_id = vec![HardwareType::Ethernet.generate()];
-        client_id.extend_from_slice(mac_address.as_slice());
-        packet.add_option(DHCPOptionClass::ClientID, &client_id.as_slice());
-        packet.add_option(DHCPOptionClass::End, &[]);
-
-        Ok(packet)
+        let offer = self
+            .add_boot_file(self.reply(packet, DHCP_MESSAGE_TYPE_OFFER, client))
+            .your_ip_address(return_ip)
+            .server_ip_address(self.boot_server)
+            .lease_times(self.address_time, self.renewal_time, self.rebinding_time);
+
+        // Requested options come last so they are dropped first if the reply
+        // is too large for the client
+        let offer_packet = self
+            .add_requested_options(offer, packet, client, return_ip)
+            .build();
+
+        Ok(offer_packet)
     }
 
-    fn handle_request_packet(
+    fn handle_decline_packet(
         &mut self,
-        packet: DHCPPacket,
-        mac_address: MACAddress,
-    ) -> Result<(DHCPPacket, Option<SocketAddr>), HandlePacketError> {
+        packet: &DHCPPacketView,
+        client: &Client,
+    ) -> Result<(), HandlePacketError> {
         let logger = logging::get_logger(module_path!());
 
-        // Get requested I.P. address
-        let requested_ip = match packet.get_option(DHCPOptionClass::AddressRequest) {
+        // Declines must name the server (RFC 2131 section 4.3.3), those meant
+        // for other servers are ignored
+        match get_value(packet, DHCPOptionClass::DHCPServerID)? {
             Some(value) => {
-                if value.len() != 4 {
-                    return Err(HandlePacketError::InvalidRequestedAddressLength);
-                } else {
-                    IPAddress::new([value[0], value[1], value[2], value[3]])
+                if value.as_ip_address() != Some(client.server_id) {
+                    return Ok(());
                 }
             }
+            None => return Err(HandlePacketError::NoServerIDInDecline),
+        }
+
+        let declined_ip = match get_value(packet, DHCPOptionClass::AddressRequest)? {
+            Some(value) => match value.as_ip_address() {
+                Some(declined_ip) => declined_ip,
+                None => return Err(HandlePacketError::NoRequestedIPInRequest),
+            },
+            None => return Err(HandlePacketError::NoRequestedIPInRequest),
+        };
+
+        // The address is in use by another device, so stop handing it out for a while
+        if self.leases.decline(declined_ip, &client.id) {
+            logging::warning!(
+                logger,
+                "{} declined {}, the address has been quarantined",
+                client,
+                declined_ip
+            );
+        } else {
+            logging::warning!(
+                logger,
+                "{} declined {} which was not given to them",
+                client,
+                declined_ip
+            );
+        }
+
+        Ok(())
+    }
+
+    fn handle_request_packet(
+        &mut self,
+        packet: &DHCPPacketView,
+        client: &Client,
+    ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
+        let server_id = match get_value(packet, DHCPOptionClass::DHCPServerID)? {
+            Some(value) => value.as_ip_address(),
+            None => None,
+        };
+
+        let requested_ip = match get_value(packet, DHCPOptionClass::AddressRequest)? {
+            Some(value) => value.as_ip_address(),
+            None => None,
+        };
+
+        // Determine the client's state (RFC 2131 section 4.3.2)
+        match server_id {
+            Some(server_id) => match requested_ip {
+                Some(requested_ip) => {
+                    self.handle_selecting_request(packet, client, server_id, requested_ip)
+                }
+                None => Err(HandlePacketError::NoRequestedIPInRequest),
+            },
             None => {
-                if packet.client_ip_address() == IPAddress::new([0, 0, 0, 0]) {
-                    return Err(HandlePacketError::NoRequestedIPInRequest);
+                if packet.client_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+                    self.handle_renewing_request(packet, client)
                 } else {
-                    // Renewing / Rebinding
-                    logging::info!(
-                        logger,
-                        "{} attempting to renew lease for {}",
-                        mac_address,
-                        packet.client_ip_address()
-                    );
-
-                    // Verify client i.p. before responding
-                    match self.reserved.get(&mac_address) {
-                        Some(ip) => {
-                            if *ip == packet.client_ip_address() {
-                                return Ok(self.generate_ack_packet(
-                                    packet,
-                                    Some(*ip),
-                                    mac_address,
-                                ));
-                            } else {
-                                return Err(HandlePacketError::InvalidRenewAddress);
-                            }
-                        }
-                        None => {
-                            if self
-                                .leases
-                                .accept_offer(packet.client_ip_address(), mac_address)
-                            {
-                                let requested_ip = packet.client_ip_address();
-                                return Ok(self.generate_ack_packet(
-                                    packet,
-                                    Some(requested_ip),
-                                    mac_address,
-                                ));
-                            } else {
-                                return Err(HandlePacketError::InvalidRenewAddress);
-                            }
+                    match requested_ip {
+                        Some(requested_ip) => {
+                            self.handle_init_reboot_request(packet, client, requested_ip)
                         }
+                        None => Err(HandlePacketError::NoRequestedIPInRequest),
                     }
                 }
             }
-        };
+        }
+    }
+
+    // The client is responding to an offer
+    fn handle_selecting_request(
+        &mut self,
+        packet: &DHCPPacketView,
+        client: &Client,
+        server_id: IPAddress,
+        requested_ip: IPAddress,
+    ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
+        let logger = logging::get_logger(module_path!());
+
+        // The client chose another server, so our offer is no longer needed
+        if server_id != client.server_id {
+            logging::info!(
+                logger,
+                "{} accepted an offer from {}, withdrawing our offer",
+                client,
+                server_id
+            );
+            self.leases.cancel_offer(&client.id);
+            return Ok(None);
+        }
 
         // See if client has reserved I.P. Address
-        match self.reserved.get(&mac_address) {
-            Some(ip_address) => {
-                // Has a reserved I.P. address
-                if requested_ip == *ip_address {
-                    // Requesting reserved I.P. address
-                    return Ok(self.generate_ack_packet(packet, Some(requested_ip), mac_address));
-                } else {
-                    // Requesting another I.P. address than one that is reserved
-                    logging::warning!(
-                        logger,
-                        "{} requested {} which is not their reserved address",
-                        mac_address,
-                        requested_ip
-                    );
-                    return Ok((self.generate_nack_packet(packet, mac_address), None));
-                }
+        if let Some(ip_address) = self.reserved_ip(client) {
+            // Has a reserved I.P. address
+            if requested_ip == ip_address {
+                // Requesting reserved I.P. address, the binding is
+                // recorded so only this client may renew it
+                self.leases.accept_offer(
+                    requested_ip,
+                    &client.id,
+                    client.relay_agent_information.as_ref(),
+                    self.address_time,
+                );
+                return Ok(Some(self.generate_ack_packet(
+                    packet,
+                    client,
+                    Some(requested_ip),
+                )));
+            } else {
+                // Requesting another I.P. address than one that is reserved
+                logging::warning!(
+                    logger,
+                    "{} requested {} which is not their reserved address",
+                    client,
+                    requested_ip
+                );
+                return Ok(Some(self.generate_nack_packet(packet, client)));
             }
-            None => {} // No reserved I.P. address
         }
 
         // Verify requested I.P. with leases
-        if self.leases.accept_offer(requested_ip, mac_address) {
-            logging::info!(
+        if self.leases.accept_offer(
+            requested_ip,
+            &client.id,
+            client.relay_agent_information.as_ref(),
+            self.address_time,
+        ) {
+            logging::info!(logger, "{} accepted lease for {}", client, requested_ip);
+            Ok(Some(self.generate_ack_packet(
+                packet,
+                client,
+                Some(requested_ip),
+            )))
+        } else {
+            logging::warning!(
                 logger,
-                "{} accepted lease for {}",
-                mac_address,
+                "{} requested {} which is an invalid address",
+                client,
                 requested_ip
             );
-            Ok(self.generate_ack_packet(packet, Some(requested_ip), mac_address))
-        } else {
+            Ok(Some(self.generate_nack_packet(packet, client)))
+        }
+    }
+
+    // The client is rebooting and wants to verify its previous address
+    fn handle_init_reboot_request(
+        &mut self,
+        packet: &DHCPPacketView,
+        client: &Client,
+        requested_ip: IPAddress,
+    ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
+        let logger = logging::get_logger(module_path!());
+
+        logging::info!(
+            logger,
+            "{} attempting to verify previous address {}",
+            client,
+            requested_ip
+        );
+
+        // Verify the client is on the correct network
+        if !self.on_link(client, requested_ip) {
             logging::warning!(
                 logger,
-                "{} requested {} which is an invalid address",
-                mac_address,
+                "{} requested {} which is not on our network",
+                client,
                 requested_ip
             );
-            Ok((self.generate_nack_packet(packet, mac_address), None))
+            return Ok(Some(self.generate_nack_packet(packet, client)));
+        }
+
+        let current_ip = match self.reserved_ip(client) {
+            Some(ip_address) => Some(ip_address),
+            None => self.leases.get_ip_address(&client.id),
+        };
+
+        match current_ip {
+            Some(ip_address) => {
+                if ip_address == requested_ip
+                    && self.leases.accept_offer(
+                        requested_ip,
+                        &client.id,
+                        client.relay_agent_information.as_ref(),
+                        self.address_time,
+                    )
+                {
+                    Ok(Some(self.generate_ack_packet(
+                        packet,
+                        client,
+                        Some(requested_ip),
+                    )))
+                } else {
+                    logging::warning!(
+                        logger,
+                        "{} requested {} but is bound to {}",
+                        client,
+                        requested_ip,
+                        ip_address
+                    );
+                    Ok(Some(self.generate_nack_packet(packet, client)))
+                }
+            }
+            None => {
+                // The address may have come from another server
+                logging::info!(
+                    logger,
+                    "No record of {} for {}, ignoring request",
+                    requested_ip,
+                    client
+                );
+                Ok(None)
+            }
         }
     }
 
+    // The client is extending its lease, either directly with us or by broadcast
+    fn handle_renewing_request(
+        &mut self,
+        packet: &DHCPPacketView,
+        client: &Client,
+    ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
+        let logger = logging::get_logger(module_path!());
+
+        let client_ip = packet.client_ip_address();
+        logging::info!(
+            logger,
+            "{} attempting to renew lease for {}",
+            client,
+            client_ip
+        );
+
+        // Renewals are sent directly to us without relay agent information,
+        // so an address reserved for a port is only renewed by the client it
+        // is bound to
+        let current_ip = match self.reserved_ip(client) {
+            Some(ip_address) => Some(ip_address),
+            None => self.leases.get_ip_address(&client.id),
+        };
+
+        match current_ip {
+            Some(ip_address) => {
+                if ip_address == client_ip
+                    && self.leases.accept_offer(
+                        client_ip,
+                        &client.id,
+                        client.relay_agent_information.as_ref(),
+                        self.address_time,
+                    )
+                {
+                    Ok(Some(self.generate_ack_packet(
+                        packet,
+                        client,
+                        Some(client_ip),
+                    )))
+                } else {
+                    logging::warning!(
+                        logger,
+                        "{} attempted to renew {} but is bound to {}",
+                        client,
+                        client_ip,
+                        ip_address
+                    );
+                    Ok(Some(self.generate_nack_packet(packet, client)))
+                }
+            }
+            None => {
+                // The lease may belong to another server
+                logging::info!(
+                    logger,
+                    "No record of {} for {}, ignoring renewal",
+                    client_ip,
+                    client
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    // BOOTP clients only get an address if they have a reservation, unless
+    // dynamic BOOTP is enabled where they are given a lease that never
+    // expires (RFC 1534 section 2)
+    fn handle_bootp_packet(
+        &mut self,
+        packet: &DHCPPacketView,
+        client: &Client,
+    ) -> Result<Option<(DHCPPacket, Option<SocketAddr>)>, HandlePacketError> {
+        let logger = logging::get_logger(module_path!());
+
+        let ip_address = match self.reserved_ip(client) {
+            Some(ip_address) => ip_address,
+            None => {
+                if !self.dynamic_bootp {
+                    logging::info!(
+                        logger,
+                        "No reservation for BOOTP client {}, ignoring request",
+                        client
+                    );
+                    return Ok(None);
+                }
+
+                let ip_address = match self.leases.get_ip_address(&client.id) {
+                    Some(ip_address) => ip_address,
+                    None => match self.allocate(client) {
+                        Some(ip_address) => ip_address,
+                        None => return Err(HandlePacketError::NoIPAddressesAvailable),
+                    },
+                };
+
+                if !self.leases.accept_offer(
+                    ip_address,
+                    &client.id,
+                    client.relay_agent_information.as_ref(),
+                    INFINITE_ADDRESS_TIME,
+                ) {
+                    return Err(HandlePacketError::NoIPAddressesAvailable);
+                }
+
+                ip_address
+            }
+        };
+
+        logging::info!(logger, "Giving {} to BOOTP client {}", ip_address, client);
+
+        let reply = self
+            .add_boot_file(echo_relay_agent_information(
+                ReplyBuilder::bootp(packet),
+                packet,
+                client,
+            ))
+            .client_ip_address(packet.client_ip_address())
+            .your_ip_address(ip_address)
+            .server_ip_address(self.boot_server);
+
+        // BOOTP clients don't send a parameter list so they recieve every
+        // option that fits
+        let reply_packet = self
+            .add_requested_options(reply, packet, client, ip_address)
+            .build();
+
+        // Replies go back through the relay or directly to a client which
+        // already knows its address, otherwise they are broadcast
+        let target = if packet.gateway_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+            Some(packet.gateway_ip_address().to_socket_addr(DHCP_SERVER_PORT))
+        } else if packet.client_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+            Some(packet.client_ip_address().to_socket_addr(DHCP_CLIENT_PORT))
+        } else {
+            None
+        };
+
+        Ok(Some((reply_packet, target)))
+    }
+
+    // Clients are placed on the subnet of the relay agent which forwarded
+    // their request, or of the address they are renewing if they sent it to
+    // us directly, otherwise on the subnet of the interface it arrived on.
+    // Trusted relays may instead name the client's link when it isn't the
+    // address they forward from, such as behind NAT or on unnumbered
+    // interfaces (RFC 3527 and RFC 3011). Every subnet in the same shared
+    // network is on the same link. Also returns if the subnet selection
+    // option was used.
+    fn select_subnets(
+        &self,
+        packet: &DHCPPacketView,
+        relay_agent_information: Option<&RelayAgentInformation>,
+        server_id: IPAddress,
+    ) -> Result<(Vec<usize>, bool), HandlePacketError> {
+        let logger = logging::get_logger(module_path!());
+
+        let mut selected_address = None;
+        let mut subnet_selection = false;
+        if self.trusted_relays.contains(&packet.gateway_ip_address()) {
+            selected_address = match relay_agent_information {
+                Some(relay_agent_information) => relay_agent_information.link_selection(),
+                None => None,
+            };
+
+            if selected_address.is_none() {
+                selected_address = match get_value(packet, DHCPOptionClass::SubnetSelection)? {
+                    Some(value) => value.as_ip_address(),
+                    None => None,
+                };
+                subnet_selection = selected_address.is_some();
+            }
+        }
+
+        let link_address = match selected_address {
+            Some(selected_address) => {
+                logging::info!(
+                    logger,
+                    "{} selected the subnet of {}",
+                    packet.gateway_ip_address(),
+                    selected_address
+                );
+                selected_address
+            }
+            None => {
+                if packet.gateway_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+                    packet.gateway_ip_address()
+                } else if packet.client_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+                    packet.client_ip_address()
+                } else {
+                    server_id
+                }
+            }
+        };
+
+        let subnet = match self
+            .subnets
+            .iter()
+            .position(|(subnet, _)| subnet.contains(link_address))
+        {
+            Some(subnet) => subnet,
+            None => return Err(HandlePacketError::NoSubnet(link_address)),
+        };
+
+        let subnets = match self.subnets[subnet].0.shared_network() {
+            Some(shared_network) => (0..self.subnets.len())
+                .filter(|i| self.subnets[*i].0.shared_network() == Some(shared_network))
+                .collect(),
+            None => vec![subnet],
+        };
+
+        Ok((subnets, subnet_selection))
+    }
+
+    fn on_link(&self, client: &Client, ip_address: IPAddress) -> bool {
+        client
+            .subnets
+            .iter()
+            .any(|subnet| self.subnets[*subnet].0.contains(ip_address))
+    }
+
+    // Each subnet on the link is tried in order until one has a free address
+    fn allocate(&mut self, client: &Client) -> Option<IPAddress> {
+        for subnet in &client.subnets {
+            if let Some(ip_address) = self.leases.allocate(&client.id, &self.subnets[*subnet].0) {
+                return Some(ip_address);
+            }
+        }
+
+        None
+    }
+
+    fn is_relay(&self, gateway_ip_address: IPAddress) -> bool {
+        if gateway_ip_address == IPAddress::new([0, 0, 0, 0]) {
+            return false;
+        }
+
+        self.trusted_relays.is_empty() || self.trusted_relays.contains(&gateway_ip_address)
+    }
+
+    // The first matching reservation on the client's link wins
+    fn reserved_ip(&self, client: &Client) -> Option<IPAddress> {
+        for reservation in &self.reservations {
+            if self.on_link(client, reservation.ip_address())
+                && reservation.matches(
+                    &client.id,
+                    &client.hardware_address,
+                    client.relay_agent_information.as_ref(),
+                )
+            {
+                return Some(reservation.ip_address());
+            }
+        }
+
+        None
+    }
+
     fn generate_ack_packet(
         &self,
-        request_packet: DHCPPacket,
+        request_packet: &DHCPPacketView,
+        client: &Client,
         requested_address: Option<IPAddress>,
-        mac_address: MACAddress,
     ) -> (DHCPPacket, Option<SocketAddr>) {
-        let mut packet = DHCPPacket::new(
-            request_packet.transaction_id(),
-            request_packet.flags(),
-            request_packet.client_ip_address(),
-            match requested_address {
-                Some(address) => address,
-                None => IPAddress::new([0, 0, 0, 0]),
-            },
-            self.our_ip,
-            request_packet.gateway_ip_address(),
-            *request_packet.client_hardware_address(),
-        );
+        let mut ack = self
+            .add_boot_file(self.reply(request_packet, DHCP_MESSAGE_TYPE_ACK, client))
+            .client_ip_address(request_packet.client_ip_address())
+            .server_ip_address(self.boot_server);
 
-        packet.add_option(DHCPOptionClass::DHCPMsgType, &[DHCP_MESSAGE_TYPE_ACK]);
-        packet.add_option(DHCPOptionClass::DHCPServerID, self.our_ip.as_slice());
-
-        if requested_address.is_some() {
-            packet.add_option(
-                DHCPOptionClass::AddressTime,
-                &u32_to_slice(self.leases.address_time()),
-            );
-            packet.add_option(
-                DHCPOptionClass::RenewalTime,
-                &u32_to_slice(self.leases.renewal_time()),
-            );
-            packet.add_option(
-                DHCPOptionClass::RebindingTime,
-                &u32_to_slice(self.leases.rebinding_time()),
+        if let Some(address) = requested_address {
+            ack = ack.your_ip_address(address).lease_times(
+                self.address_time,
+                self.renewal_time,
+                self.rebinding_time,
             );
         }
 
-        packet.add_option(DHCPOptionClass::SubnetMask, self.subnet_mask.as_slice());
-        packet.add_option(
-            DHCPOptionClass::BroadcastAddress,
-            self.broadcast_address.as_slice(),
-        );
-        packet.add_option(DHCPOptionClass::Gateways, self.gateway_ip.as_slice());
-        let mut dns = Vec::from(self.dns.as_slice());
-        dns.extend_from_slice(self.dns_alternative.as_slice());
-        packet.add_option(DHCPOptionClass::DomainServer, dns.as_slice());
-        let mut client_id = vec![HardwareType::Ethernet.generate()];
-        client_id.extend_from_slice(mac_address.as_slice());
-        packet.add_option(DHCPOptionClass::ClientID, &client_id.as_slice());
-        packet.add_option(DHCPOptionClass::End, &[]);
+        let packet = self
+            .add_requested_options(
+                ack,
+                request_packet,
+                client,
+                match requested_address {
+                    Some(address) => address,
+                    None => request_packet.client_ip_address(),
+                },
+            )
+            .build();
 
         (
             packet,
@@ -452,27 +949,137 @@ impl DHCPServer {
         )
     }
 
-    fn generate_nack_packet(
+    // Adds the configured options the client asked for in the order it asked for
+    // them, clients which don't send a parameter list recieve every option.
+    // The options are those of the subnet "ip_address" is on.
+    fn add_requested_options(
         &self,
-        request_packet: DHCPPacket,
-        mac_address: MACAddress,
-    ) -> DHCPPacket {
-        let mut packet = DHCPPacket::new(
-            request_packet.transaction_id(),
-            request_packet.flags(),
-            IPAddress::new([0, 0, 0, 0]),
-            IPAddress::new([0, 0, 0, 0]),
-            IPAddress::new([0, 0, 0, 0]),
-            request_packet.gateway_ip_address(),
-            *request_packet.client_hardware_address(),
+        mut reply: ReplyBuilder,
+        request_packet: &DHCPPacketView,
+        client: &Client,
+        ip_address: IPAddress,
+    ) -> ReplyBuilder {
+        let subnet = match client
+            .subnets
+            .iter()
+            .find(|subnet| self.subnets[**subnet].0.contains(ip_address))
+        {
+            Some(subnet) => *subnet,
+            None => client.subnets[0],
+        };
+        let options = &self.subnets[subnet].1;
+
+        let parameter_list = match request_packet.get_value(DHCPOptionClass::ParameterList) {
+            Ok(Some(value)) => value.as_u8_list().map(|codes| codes.to_vec()),
+            _ => None,
+        };
+
+        match parameter_list {
+            Some(parameter_list) => {
+                let mut added = Vec::new();
+                for code in &parameter_list {
+                    let option_class = DHCPOptionClass::parse(*code);
+                    if added.contains(&option_class) {
+                        continue;
+                    }
+
+                    for (class, value) in options {
+                        if *class == option_class {
+                            reply = reply.option(*class, value);
+                            added.push(option_class);
+                            break;
+                        }
+                    }
+                }
+            }
+            None => {
+                for (class, value) in options {
+                    reply = reply.option(*class, value);
+                }
+            }
+        }
+
+        reply
+    }
+
+    fn add_boot_file(&self, reply: ReplyBuilder) -> ReplyBuilder {
+        match &self.boot_file {
+            Some(boot_file) => reply.boot_file(boot_file.as_bytes()),
+            None => reply,
+        }
+    }
+
+    // The subnet selection option is only echoed if it chose the client's
+    // subnet, telling the client it was honoured (RFC 3011 section 3)
+    fn reply(
+        &self,
+        request_packet: &DHCPPacketView,
+        message_type: u8,
+        client: &Client,
+    ) -> ReplyBuilder {
+        let reply = echo_relay_agent_information(
+            ReplyBuilder::new(request_packet, message_type, client.server_id),
+            request_packet,
+            client,
         );
+        if client.subnet_selection {
+            reply.subnet_selection(request_packet)
+        } else {
+            reply
+        }
+    }
 
-        packet.add_option(DHCPOptionClass::DHCPMsgType, &[DHCP_MESSAGE_TYPE_NACK]);
-        packet.add_option(DHCPOptionClass::DHCPServerID, self.our_ip.as_slice());
-        packet.add_option(DHCPOptionClass::ClientID, mac_address.as_slice());
-        packet.add_option(DHCPOptionClass::End, &[]);
+    fn generate_nack_packet(
+        &self,
+        request_packet: &DHCPPacketView,
+        client: &Client,
+    ) -> (DHCPPacket, Option<SocketAddr>) {
+        let packet = self
+            .reply(request_packet, DHCP_MESSAGE_TYPE_NACK, client)
+            .build();
 
-        packet
+        // Relayed NAKs go back through the relay, otherwise they are broadcast
+        (
+            packet,
+            if request_packet.gateway_ip_address() != IPAddress::new([0, 0, 0, 0]) {
+                Some(
+                    request_packet
+                        .gateway_ip_address()
+                        .to_socket_addr(DHCP_SERVER_PORT),
+                )
+            } else {
+                None
+            },
+        )
+    }
+}
+
+fn get_value(
+    packet: &DHCPPacketView,
+    option_class: DHCPOptionClass,
+) -> Result<Option<OptionValue>, HandlePacketError> {
+    match packet.get_value(option_class) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(HandlePacketError::MalformedOption(option_class, error)),
+    }
+}
+
+// Relay agent information is only kept for relayed requests from trusted
+// relays, only those get it back
+fn echo_relay_agent_information(
+    reply: ReplyBuilder,
+    request_packet: &DHCPPacketView,
+    client: &Client,
+) -> ReplyBuilder {
+    match client.relay_agent_information {
+        Some(_) => reply.relay_agent_information(request_packet),
+        None => reply,
+    }
+}
+
+impl std::fmt::Display for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
     }
 }
 
@@ -484,21 +1091,80 @@ impl std::fmt::Display for HandlePacketError {
             f,
             "{}",
             match self {
-                HandlePacketError::MalformedOption => format!("Malformed option"),
-                HandlePacketError::NoMsgType => format!("No message type"),
-                HandlePacketError::InvalidHardwareType(hardware_type) =>
-                    format!("Invalid hardware type ({})", hardware_type),
-                HandlePacketError::InvalidHardwareAddressLength(address_length) =>
-                    format!("Invalid address length ({})", address_length),
+                HandlePacketError::MalformedOption(option_class, error) =>
+                    format!("Malformed {} option ({})", option_class, error),
+                HandlePacketError::MalformedRelayAgentInformation(error) =>
+                    format!("Malformed relay agent information ({})", error),
+                HandlePacketError::NoMsgType => "No message type".to_owned(),
+                HandlePacketError::InvalidHardwareAddressLength(hardware_type, address_length) =>
+                    format!(
+                        "Invalid address length for {} ({})",
+                        hardware_type, address_length
+                    ),
+                HandlePacketError::NoClientID(hardware_type) =>
+                    format!("No client identifier from {} client", hardware_type),
                 HandlePacketError::NoIPAddressesAvailable =>
-                    format!("No I.P. Addresses are available"),
-                HandlePacketError::InvalidRequestedAddressLength =>
-                    format!("Invalid requested address length"),
+                    "No I.P. Addresses are available".to_owned(),
                 HandlePacketError::NoRequestedIPInRequest =>
-                    format!("No requested address in request"),
-                HandlePacketError::InvalidRenewAddress => format!("Invalid renew address"),
-                HandlePacketError::DeclineMessageRecieved => format!("Decline packet recieved"),
+                    "No requested address in request".to_owned(),
+                HandlePacketError::NoServerIDInDecline =>
+                    "No server identifier in decline".to_owned(),
+                HandlePacketError::NoSubnet(link_address) =>
+                    format!("No subnet is configured for {}", link_address),
             }
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dhcp::{ParseMode, MAGIC, MAGIC_OFFSET};
+
+    // A DISCOVER carrying relay agent information, relayed by "gateway"
+    fn discover(gateway: [u8; 4]) -> Vec<u8> {
+        let mut packet = vec![0; MAGIC_OFFSET];
+        packet[0] = 1;
+        packet[1] = 1;
+        packet[2] = 6;
+        packet[24..28].copy_from_slice(&gateway);
+        packet[28..34].copy_from_slice(&[0, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        packet.extend_from_slice(&MAGIC);
+        packet.extend_from_slice(&[53, 1, 1, 82, 5, 1, 3, b'e', b't', b'h', 255]);
+        packet
+    }
+
+    fn offer_relay_agent_information(gateway: [u8; 4], name: &str) -> Option<Vec<u8>> {
+        let path =
+            std::env::temp_dir().join(format!("dhcp-server-{}-{}", std::process::id(), name));
+        let mut server =
+            DHCPServer::new(&crate::config::Configuration::test(path.clone())).unwrap();
+
+        let request = discover(gateway);
+        let request = DHCPPacketView::parse(&request, ParseMode::Strict).unwrap();
+        let (reply, _) = server
+            .handle_packet(&request, Some(IPAddress::new([192, 168, 1, 1])))
+            .unwrap()
+            .unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let reply = reply.generate();
+        let reply = DHCPPacketView::parse(&reply, ParseMode::Strict).unwrap();
+        reply
+            .get_option(DHCPOptionClass::RelayAgentInformation)
+            .map(|value| value.to_vec())
+    }
+
+    #[test]
+    fn relay_agent_information_is_echoed_to_relays() {
+        assert_eq!(
+            offer_relay_agent_information([192, 168, 1, 2], "relayed"),
+            Some(vec![1, 3, b'e', b't', b'h'])
+        );
+    }
+
+    #[test]
+    fn relay_agent_information_is_not_echoed_to_direct_clients() {
+        assert_eq!(offer_relay_agent_information([0, 0, 0, 0], "direct"), None);
+    }
+}
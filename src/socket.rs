@@ -0,0 +1,188 @@
+use crate::IPAddress;
+use std::{
+    io::{Error, ErrorKind},
+    mem::{size_of, zeroed},
+    net::{SocketAddr, UdpSocket},
+    os::unix::io::AsRawFd,
+};
+
+// A U.D.P. socket listening on every interface which reports the interface
+// each packet arrived on and sends replies back out of it (IP_PKTINFO)
+pub struct DHCPSocket {
+    socket: UdpSocket,
+}
+
+// Where a packet arrived, the local address is the address of the interface
+// for broadcasts
+#[derive(Debug, Clone, Copy)]
+pub struct PacketInfo {
+    interface_index: u32,
+    local_address: IPAddress,
+}
+
+// Large and aligned enough for one in_pktinfo control message
+type ControlBuffer = [u64; 8];
+
+impl DHCPSocket {
+    pub fn bind(port: u16) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(format!("0.0.0.0:{}", port))?;
+        socket.set_broadcast(true)?;
+
+        let enable: libc::c_int = 1;
+        if unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_PKTINFO,
+                &enable as *const libc::c_int as *const libc::c_void,
+                size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        } < 0
+        {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(DHCPSocket { socket })
+    }
+
+    // The packet info is missing if the system didn't report it
+    pub fn recv_from(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<(usize, SocketAddr, Option<PacketInfo>), Error> {
+        let mut source: libc::sockaddr_in = unsafe { zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buffer.len(),
+        };
+        let mut control: ControlBuffer = [0; 8];
+
+        let mut message: libc::msghdr = unsafe { zeroed() };
+        message.msg_name = &mut source as *mut libc::sockaddr_in as *mut libc::c_void;
+        message.msg_namelen = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        message.msg_controllen = size_of::<ControlBuffer>() as _;
+
+        let length = unsafe { libc::recvmsg(self.socket.as_raw_fd(), &mut message, 0) };
+        if length < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        let mut packet_info = None;
+        unsafe {
+            let mut header = libc::CMSG_FIRSTHDR(&message);
+            while !header.is_null() {
+                if (*header).cmsg_level == libc::IPPROTO_IP
+                    && (*header).cmsg_type == libc::IP_PKTINFO
+                {
+                    let info = std::ptr::read_unaligned(
+                        libc::CMSG_DATA(header) as *const libc::in_pktinfo
+                    );
+                    packet_info = Some(PacketInfo {
+                        interface_index: info.ipi_ifindex as u32,
+                        local_address: IPAddress::new(info.ipi_spec_dst.s_addr.to_ne_bytes()),
+                    });
+                }
+
+                header = libc::CMSG_NXTHDR(&message, header);
+            }
+        }
+
+        let source = IPAddress::new(source.sin_addr.s_addr.to_ne_bytes())
+            .to_socket_addr(u16::from_be(source.sin_port));
+
+        Ok((length as usize, source, packet_info))
+    }
+
+    // Replies are sent from the address the request arrived at. Broadcast and
+    // link-local replies are sent out of the interface the request arrived
+    // on so they reach the right network, other replies such as those to a
+    // relay agent follow the routing table.
+    pub fn send_to(
+        &self,
+        buffer: &[u8],
+        target: SocketAddr,
+        packet_info: Option<PacketInfo>,
+    ) -> Result<usize, Error> {
+        let packet_info = match packet_info {
+            Some(packet_info) => packet_info,
+            None => return self.socket.send_to(buffer, target),
+        };
+
+        let target = match target {
+            SocketAddr::V4(target) => target,
+            SocketAddr::V6(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Only I.P. version 4 targets are supported",
+                ))
+            }
+        };
+
+        let mut destination: libc::sockaddr_in = unsafe { zeroed() };
+        destination.sin_family = libc::AF_INET as libc::sa_family_t;
+        destination.sin_port = target.port().to_be();
+        destination.sin_addr.s_addr = u32::from(*target.ip()).to_be();
+
+        let mut iov = libc::iovec {
+            iov_base: buffer.as_ptr() as *mut libc::c_void,
+            iov_len: buffer.len(),
+        };
+        let mut control: ControlBuffer = [0; 8];
+
+        let mut message: libc::msghdr = unsafe { zeroed() };
+        message.msg_name = &mut destination as *mut libc::sockaddr_in as *mut libc::c_void;
+        message.msg_namelen = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        message.msg_controllen =
+            unsafe { libc::CMSG_SPACE(size_of::<libc::in_pktinfo>() as u32) } as _;
+
+        let length = unsafe {
+            let header = libc::CMSG_FIRSTHDR(&message);
+            (*header).cmsg_level = libc::IPPROTO_IP;
+            (*header).cmsg_type = libc::IP_PKTINFO;
+            (*header).cmsg_len = libc::CMSG_LEN(size_of::<libc::in_pktinfo>() as u32) as _;
+
+            let mut info: libc::in_pktinfo = zeroed();
+            if target.ip().is_broadcast() || target.ip().is_link_local() {
+                info.ipi_ifindex = packet_info.interface_index as libc::c_int;
+            }
+            info.ipi_spec_dst.s_addr = packet_info.local_address.to_u32().to_be();
+            std::ptr::write_unaligned(libc::CMSG_DATA(header) as *mut libc::in_pktinfo, info);
+
+            libc::sendmsg(self.socket.as_raw_fd(), &message, 0)
+        };
+        if length < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(length as usize)
+    }
+}
+
+impl PacketInfo {
+    pub fn interface_index(&self) -> u32 {
+        self.interface_index
+    }
+
+    pub fn local_address(&self) -> IPAddress {
+        self.local_address
+    }
+}
+
+// Returns None if there is no interface with the name
+pub fn interface_index(name: &str) -> Option<u32> {
+    let name = match std::ffi::CString::new(name) {
+        Ok(name) => name,
+        Err(_) => return None,
+    };
+
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => None,
+        index => Some(index),
+    }
+}
@@ -18,3 +18,22 @@ pub fn u32_to_slice(value: u32) -> [u8; 4] {
         (value & 0xFF) as u8,
     ]
 }
+
+// Accepts "0A0B0C", "0x0A0B0C" or "0A:0B:0C"
+#[allow(clippy::manual_is_multiple_of)] // is_multiple_of needs Rust 1.87
+pub fn parse_hex(str: &str) -> Option<Vec<u8>> {
+    let hex: String = str
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| *c != ':')
+        .collect();
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    for i in (0..hex.len()).step_by(2) {
+        bytes.push(u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()?);
+    }
+    Some(bytes)
+}